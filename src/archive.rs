@@ -0,0 +1,181 @@
+//! Unpacks verified release archives the same way `zed::download_file` does,
+//! for downloads that have to be checked before anything lands in the work dir.
+
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zed_extension_api::{self as zed, Result};
+
+/// Unpacks `bytes` into `destination`: a directory for archives, a file otherwise.
+pub fn extract(bytes: &[u8], file_type: zed::DownloadedFileType, destination: &str) -> Result<()> {
+    let destination = Path::new(destination);
+    match file_type {
+        zed::DownloadedFileType::GzipTar => untar(GzDecoder::new(bytes), destination),
+        zed::DownloadedFileType::Zip => unzip(bytes, destination),
+        zed::DownloadedFileType::Gzip => write_file(destination, &gunzip(bytes)?),
+        zed::DownloadedFileType::Uncompressed => write_file(destination, bytes),
+    }
+}
+
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut contents)
+        .map_err(|e| format!("failed to decompress archive: {e}"))?;
+    Ok(contents)
+}
+
+fn untar(reader: impl Read, destination: &Path) -> Result<()> {
+    create_dir(destination)?;
+    let mut archive = tar::Archive::new(reader);
+    // WASI cannot set file times or ownership.
+    archive.set_preserve_mtime(false);
+    archive.set_preserve_permissions(false);
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("failed to read archive: {e}"))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("failed to read archive: {e}"))?;
+        // `unpack_in` refuses entries that would end up outside `destination`.
+        let unpacked = entry
+            .unpack_in(destination)
+            .map_err(|e| format!("failed to extract archive: {e}"))?;
+        if !unpacked {
+            let path = entry.path().map(|path| path.display().to_string());
+            return Err(format!(
+                "refusing to extract unsafe archive entry {:?}",
+                path.unwrap_or_default()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn unzip(bytes: &[u8], destination: &Path) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("failed to read archive: {e}"))?;
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("failed to read archive: {e}"))?;
+        let Some(name) = entry.enclosed_name() else {
+            return Err(format!(
+                "refusing to extract unsafe archive entry {:?}",
+                entry.name()
+            ));
+        };
+        let path = destination.join(name);
+        if entry.is_dir() {
+            create_dir(&path)?;
+            continue;
+        }
+        let mut contents = Vec::new();
+        entry
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("failed to extract {}: {e}", entry.name()))?;
+        write_file(&path, &contents)?;
+    }
+    Ok(())
+}
+
+fn create_dir(path: &Path) -> Result<()> {
+    fs::create_dir_all(path).map_err(|e| format!("failed to create {}: {e}", path.display()))
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        create_dir(parent)?;
+    }
+    fs::write(path, contents).map_err(|e| format!("failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("neocmake-archive-{name}-{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    fn tar_gz(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (name, contents) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *contents).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn extracts_tar_gz_with_long_names() {
+        let dir = scratch_dir("tar");
+        let long_name = format!("{}/neocmakelsp", "nested".repeat(30));
+        let archive = tar_gz(&[("neocmakelsp", b"binary"), (&long_name, b"long")]);
+
+        extract(
+            &archive,
+            zed::DownloadedFileType::GzipTar,
+            dir.to_str().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(fs::read(dir.join("neocmakelsp")).unwrap(), b"binary");
+        assert_eq!(fs::read(dir.join(&long_name)).unwrap(), b"long");
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn extracts_zip() {
+        let dir = scratch_dir("zip");
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        writer.start_file("bin/neocmakelsp.exe", options).unwrap();
+        writer.write_all(b"binary").unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+
+        extract(
+            &archive,
+            zed::DownloadedFileType::Zip,
+            dir.to_str().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read(dir.join("bin/neocmakelsp.exe")).unwrap(),
+            b"binary"
+        );
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn rejects_zip_entries_outside_destination() {
+        let dir = scratch_dir("zip-slip");
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("../escaped", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"nope").unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+
+        let result = extract(
+            &archive,
+            zed::DownloadedFileType::Zip,
+            dir.to_str().unwrap(),
+        );
+
+        assert!(result.unwrap_err().contains("unsafe archive entry"));
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,282 @@
+//! Picks the neocmakelsp release asset that runs on the host.
+
+use zed::process::Command;
+use zed_extension_api::{self as zed, Result};
+
+/// The platform neocmakelsp has to run on.
+pub struct Host {
+    pub os: zed::Os,
+    /// Architecture spellings used in release asset names, e.g. `x86_64`/`amd64`.
+    pub arch: &'static [&'static str],
+    /// Whether the host's C library is musl rather than glibc (Linux only).
+    pub musl: bool,
+}
+
+impl Host {
+    pub fn detect() -> Self {
+        let (os, arch) = zed::current_platform();
+        let mut host = Self {
+            os,
+            arch: match arch {
+                zed::Architecture::Aarch64 => &["aarch64", "arm64"],
+                zed::Architecture::X86 => &["i686", "i586", "i386"],
+                zed::Architecture::X8664 => &["x86_64", "amd64", "x64"],
+            },
+            musl: false,
+        };
+
+        if let zed::Os::Linux = os {
+            // Zed only distinguishes three architectures, so ask the kernel for the rest.
+            if let Some(machine) = run("uname", &["-m"]) {
+                host.arch = match machine.trim() {
+                    "armv7l" | "armv7" | "armhf" => &["armv7", "armhf"],
+                    "riscv64" => &["riscv64gc", "riscv64"],
+                    "ppc64le" => &["powerpc64le", "ppc64le"],
+                    "s390x" => &["s390x"],
+                    _ => host.arch,
+                };
+            }
+            host.musl = run("ldd", &["--version"]).is_some_and(|output| output.contains("musl"));
+        }
+
+        host
+    }
+
+    pub fn describe(&self) -> String {
+        let os = match self.os {
+            zed::Os::Mac => "macos",
+            zed::Os::Linux if self.musl => "linux-musl",
+            zed::Os::Linux => "linux-gnu",
+            zed::Os::Windows => "windows",
+        };
+        format!("{} {os}", self.arch[0])
+    }
+}
+
+/// Runs a command and returns its combined stdout and stderr.
+fn run(command: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(command)
+        .args(args.iter().copied())
+        .output()
+        .ok()?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Some(text)
+}
+
+/// Returns the asset named `asset_name`, or the best match for `host`.
+pub fn select<'a>(
+    assets: &'a [zed::GithubReleaseAsset],
+    host: &Host,
+    asset_name: Option<&str>,
+) -> Result<&'a zed::GithubReleaseAsset> {
+    let found = match asset_name {
+        Some(asset_name) => assets.iter().find(|asset| asset.name == asset_name),
+        None => assets
+            .iter()
+            .filter_map(|asset| Some((score(&asset.name, host)?, asset)))
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, asset)| asset),
+    };
+
+    found.ok_or_else(|| {
+        let available = assets
+            .iter()
+            .map(|asset| asset.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        match asset_name {
+            Some(asset_name) => {
+                format!("no release asset named {asset_name:?}, available assets: {available}")
+            }
+            None => format!(
+                "no release asset for {}, available assets: {available}",
+                host.describe()
+            ),
+        }
+    })
+}
+
+/// Scores how well an asset fits the host; `None` if it cannot run there.
+fn score(name: &str, host: &Host) -> Option<u32> {
+    if !is_archive(name) {
+        return None;
+    }
+    let tokens: Vec<&str> = name.split(['-', '.']).collect();
+    let has = |token: &str| tokens.contains(&token);
+
+    let os_matches = match host.os {
+        zed::Os::Mac => has("darwin") || has("macos") || has("apple"),
+        zed::Os::Linux => has("linux"),
+        zed::Os::Windows => has("windows"),
+    };
+    if !os_matches {
+        return None;
+    }
+
+    let mut score = if host.arch.iter().any(|arch| has(arch)) {
+        4
+    } else if matches!(host.os, zed::Os::Mac) && has("universal") {
+        3
+    } else {
+        return None;
+    };
+
+    if let zed::Os::Linux = host.os {
+        let gnu = tokens.iter().any(|token| token.starts_with("gnu"));
+        let musl = tokens.iter().any(|token| token.starts_with("musl"));
+        score += match (host.musl, gnu, musl) {
+            // glibc builds do not run on musl hosts.
+            (true, true, _) => return None,
+            (true, _, true) | (false, true, _) => 2,
+            // Statically linked musl builds still run on glibc hosts.
+            (false, _, true) => 1,
+            _ => 0,
+        };
+    }
+
+    Some(score)
+}
+
+fn is_archive(name: &str) -> bool {
+    ![".sha256", ".sha256sum", ".sig", ".asc", ".txt", ".json"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+/// Returns how an asset has to be unpacked, based on its file name.
+pub fn file_type(name: &str) -> zed::DownloadedFileType {
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        zed::DownloadedFileType::GzipTar
+    } else if name.ends_with(".zip") {
+        zed::DownloadedFileType::Zip
+    } else if name.ends_with(".gz") {
+        zed::DownloadedFileType::Gzip
+    } else {
+        zed::DownloadedFileType::Uncompressed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const X86_64: &[&str] = &["x86_64", "amd64", "x64"];
+    const AARCH64: &[&str] = &["aarch64", "arm64"];
+    const ARMV7: &[&str] = &["armv7", "armhf"];
+
+    fn assets(names: &[&str]) -> Vec<zed::GithubReleaseAsset> {
+        names
+            .iter()
+            .map(|name| zed::GithubReleaseAsset {
+                name: name.to_string(),
+                download_url: format!("https://example.com/{name}"),
+            })
+            .collect()
+    }
+
+    fn linux(arch: &'static [&'static str], musl: bool) -> Host {
+        Host {
+            os: zed::Os::Linux,
+            arch,
+            musl,
+        }
+    }
+
+    fn selected(names: &[&str], host: &Host) -> Result<String> {
+        select(&assets(names), host, None).map(|asset| asset.name.clone())
+    }
+
+    const LINUX_ASSETS: &[&str] = &[
+        "neocmakelsp-x86_64-unknown-linux-gnu.tar.gz",
+        "neocmakelsp-x86_64-unknown-linux-gnu.tar.gz.sha256",
+        "neocmakelsp-x86_64-unknown-linux-musl.tar.gz",
+        "neocmakelsp-aarch64-unknown-linux-gnu.tar.gz",
+        "neocmakelsp-armv7-unknown-linux-gnueabihf.tar.gz",
+    ];
+
+    #[test]
+    fn musl_host_rejects_gnu_builds() {
+        assert_eq!(
+            selected(LINUX_ASSETS, &linux(X86_64, true)).unwrap(),
+            "neocmakelsp-x86_64-unknown-linux-musl.tar.gz"
+        );
+        assert!(selected(LINUX_ASSETS, &linux(AARCH64, true)).is_err());
+    }
+
+    #[test]
+    fn glibc_host_prefers_gnu_over_musl() {
+        assert_eq!(
+            selected(LINUX_ASSETS, &linux(X86_64, false)).unwrap(),
+            "neocmakelsp-x86_64-unknown-linux-gnu.tar.gz"
+        );
+        assert_eq!(
+            selected(
+                &["neocmakelsp-x86_64-unknown-linux-musl.tar.gz"],
+                &linux(X86_64, false)
+            )
+            .unwrap(),
+            "neocmakelsp-x86_64-unknown-linux-musl.tar.gz"
+        );
+    }
+
+    #[test]
+    fn armv7_matches_gnueabihf() {
+        assert_eq!(
+            selected(LINUX_ASSETS, &linux(ARMV7, false)).unwrap(),
+            "neocmakelsp-armv7-unknown-linux-gnueabihf.tar.gz"
+        );
+    }
+
+    #[test]
+    fn mac_prefers_arch_specific_over_universal() {
+        let mac = Host {
+            os: zed::Os::Mac,
+            arch: AARCH64,
+            musl: false,
+        };
+        let names = [
+            "neocmakelsp-universal-apple-darwin.tar.gz",
+            "neocmakelsp-aarch64-apple-darwin.tar.gz",
+            "neocmakelsp-x86_64-apple-darwin.tar.gz",
+        ];
+        assert_eq!(
+            selected(&names, &mac).unwrap(),
+            "neocmakelsp-aarch64-apple-darwin.tar.gz"
+        );
+        assert_eq!(
+            selected(&[names[0], names[2]], &mac).unwrap(),
+            "neocmakelsp-universal-apple-darwin.tar.gz"
+        );
+    }
+
+    #[test]
+    fn asset_name_overrides_detection() {
+        let assets = assets(LINUX_ASSETS);
+        let asset = select(
+            &assets,
+            &linux(X86_64, false),
+            Some("neocmakelsp-x86_64-unknown-linux-musl.tar.gz"),
+        )
+        .unwrap();
+        assert_eq!(asset.name, "neocmakelsp-x86_64-unknown-linux-musl.tar.gz");
+    }
+
+    #[test]
+    fn errors_list_available_assets() {
+        let assets = assets(&[
+            "neocmakelsp-x86_64-unknown-linux-gnu.tar.gz",
+            "neocmakelsp-x86_64-pc-windows-msvc.zip",
+        ]);
+
+        let error = select(&assets, &linux(AARCH64, false), None).unwrap_err();
+        assert_eq!(
+            error,
+            "no release asset for aarch64 linux-gnu, available assets: \
+             neocmakelsp-x86_64-unknown-linux-gnu.tar.gz, neocmakelsp-x86_64-pc-windows-msvc.zip"
+        );
+
+        let error = select(&assets, &linux(X86_64, false), Some("neocmakelsp.zip")).unwrap_err();
+        assert!(error.starts_with("no release asset named \"neocmakelsp.zip\", available assets: "));
+    }
+}
@@ -0,0 +1,133 @@
+use sha2::{Digest, Sha256};
+use zed_extension_api as zed;
+
+use crate::install_error::InstallError;
+use crate::mirror;
+
+/// Returns the SHA-256 an asset must match: the user-provided one or the
+/// checksum published alongside the release asset.
+pub fn expected(
+    release: &zed::GithubRelease,
+    asset: &zed::GithubReleaseAsset,
+    expected: Option<&str>,
+    required: bool,
+) -> Result<Option<String>, InstallError> {
+    let expected = match expected {
+        Some(expected) => Some(expected.trim().to_lowercase()),
+        None => published_checksum(release, &asset.name)?,
+    };
+    if expected.is_none() && required {
+        return Err(InstallError::Checksum(format!(
+            "no SHA-256 checksum is available for {}, refusing to install it",
+            asset.name
+        )));
+    }
+    Ok(expected)
+}
+
+/// Checks `archive` against the `expected` SHA-256.
+pub fn verify(asset_name: &str, archive: &[u8], expected: &str) -> Result<(), InstallError> {
+    let actual = hex_digest(archive);
+    if actual != expected {
+        return Err(InstallError::Checksum(format!(
+            "checksum mismatch for {asset_name}: expected {expected}, got {actual}"
+        )));
+    }
+    Ok(())
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Looks up the checksum of `asset_name` in the release's checksum assets.
+fn published_checksum(
+    release: &zed::GithubRelease,
+    asset_name: &str,
+) -> Result<Option<String>, InstallError> {
+    let Some(checksum_asset) = release.assets.iter().find(|asset| {
+        let name = asset.name.to_lowercase();
+        name == format!("{}.sha256", asset_name.to_lowercase())
+            || name == format!("{}.sha256sum", asset_name.to_lowercase())
+            || name.contains("sha256sums")
+            || name.starts_with("checksums")
+    }) else {
+        return Ok(None);
+    };
+
+    let contents = mirror::fetch(&checksum_asset.download_url)
+        .map_err(|e| InstallError::fetch(&checksum_asset.download_url, e))?;
+    Ok(parse_checksums(
+        &String::from_utf8_lossy(&contents),
+        asset_name,
+    ))
+}
+
+/// Parses `sha256sum`-style output (`<hash>  [*]<file>`), accepting a bare hash
+/// for single-file checksum assets.
+fn parse_checksums(contents: &str, asset_name: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        if hash.len() != 64 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+        match parts.next() {
+            None => Some(hash.to_lowercase()),
+            Some(file) => {
+                let file = file.trim_start_matches('*');
+                let file = file.rsplit('/').next().unwrap_or(file);
+                (file == asset_name).then(|| hash.to_lowercase())
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_known_answers() {
+        assert_eq!(
+            hex_digest(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex_digest(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex_digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn verify_rejects_mismatch() {
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert!(verify("neocmakelsp", b"", empty).is_ok());
+        let error = verify("neocmakelsp", b"abc", empty).unwrap_err();
+        assert!(matches!(error, InstallError::Checksum(_)));
+    }
+
+    #[test]
+    fn parses_sha256sum_output() {
+        let hash = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let sums = format!(
+            "{}  neocmakelsp-x86_64-apple-darwin.tar.gz\n{}  *dist/neocmakelsp-x86_64-unknown-linux-gnu.tar.gz\n",
+            "0".repeat(64),
+            hash.to_uppercase()
+        );
+        assert_eq!(
+            parse_checksums(&sums, "neocmakelsp-x86_64-unknown-linux-gnu.tar.gz").as_deref(),
+            Some(hash)
+        );
+        assert_eq!(parse_checksums(&sums, "neocmakelsp.zip"), None);
+        assert_eq!(
+            parse_checksums(&format!("{hash}\n"), "any").as_deref(),
+            Some(hash)
+        );
+        assert_eq!(parse_checksums("not-a-hash  any", "any"), None);
+    }
+}
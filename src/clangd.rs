@@ -0,0 +1,61 @@
+//! Points clangd at the `compile_commands.json` of the project's CMake build.
+
+use zed::settings::LspSettings;
+use zed_extension_api::{self as zed, serde_json};
+
+use crate::cmake_cache::{self, CMakeCache};
+use crate::settings::NeoCMakeSettings;
+
+/// Key of Zed's built-in C/C++ language server.
+pub const LANGUAGE_SERVER_ID: &str = "clangd";
+
+/// clangd initialization options naming the active build directory as the compilation database,
+/// or `None` when `lsp.clangd` already chooses one.
+pub fn options(worktree: &zed::Worktree, settings: &NeoCMakeSettings) -> Option<serde_json::Value> {
+    if user_compilation_database(worktree) {
+        return None;
+    }
+    let build_dir = cmake_cache::active_build_dir(worktree, settings);
+    let path = if build_dir.starts_with('/') || build_dir.contains(':') {
+        build_dir
+    } else {
+        format!("{}/{build_dir}", worktree.root_path())
+    };
+    Some(serde_json::json!({ "compilationDatabasePath": path }))
+}
+
+/// A warning when the configured build does not write `compile_commands.json`.
+pub fn export_warning(worktree: &zed::Worktree, settings: &NeoCMakeSettings) -> Option<String> {
+    if user_compilation_database(worktree) {
+        return None;
+    }
+    let cache = CMakeCache::for_worktree(worktree, settings)?;
+    (!cache.is_on("CMAKE_EXPORT_COMPILE_COMMANDS")).then(|| {
+        format!(
+            "CMAKE_EXPORT_COMPILE_COMMANDS is off in {}, so clangd has no compile_commands.json; \
+             set it to ON and reconfigure",
+            cache.build_dir
+        )
+    })
+}
+
+/// Whether `lsp.clangd` sets `-compile-commands-dir` or `compilationDatabasePath`.
+fn user_compilation_database(worktree: &zed::Worktree) -> bool {
+    let Ok(lsp_settings) = LspSettings::for_worktree(LANGUAGE_SERVER_ID, worktree) else {
+        return false;
+    };
+    let in_arguments = lsp_settings
+        .binary
+        .and_then(|binary| binary.arguments)
+        .is_some_and(|arguments| {
+            arguments.iter().any(|argument| {
+                argument
+                    .trim_start_matches('-')
+                    .starts_with("compile-commands-dir")
+            })
+        });
+    let in_options = lsp_settings
+        .initialization_options
+        .is_some_and(|options| options.get("compilationDatabasePath").is_some());
+    in_arguments || in_options
+}
@@ -1,9 +1,43 @@
+mod archive;
+mod assets;
+mod checksum;
+mod clangd;
+mod cmake_cache;
+mod cmake_language_server;
+mod env;
+mod install_error;
+mod install_log;
+mod json_schema;
+mod labels;
+mod mirror;
+mod options;
+mod presets;
+mod project_config;
+mod release;
+mod settings;
+mod source;
+mod state;
+mod store;
+
+use cmake_cache::CMakeCache;
+use cmake_language_server::CMakeLanguageServer;
+use install_error::InstallError;
+use options::Layer;
+use release::VersionPin;
+use settings::NeoCMakeSettings;
+use state::ReleaseState;
 use std::fs;
+use zed::settings::LspSettings;
 use zed::LanguageServerId;
 use zed_extension_api::{self as zed, serde_json, Result};
 
+const SERVER_NAME: &str = "neocmakelsp";
+/// Oldest neocmakelsp that understands the initialization options we send.
+const MIN_SERVER_VERSION: semver::Version = semver::Version::new(0, 7, 0);
+
 struct NeoCMakeExt {
     cached_binary_path: Option<String>,
+    cmake_language_server: CMakeLanguageServer,
 }
 
 impl NeoCMakeExt {
@@ -11,95 +45,321 @@ impl NeoCMakeExt {
         &mut self,
         language_server_id: &LanguageServerId,
         worktree: &zed::Worktree,
+        settings: &NeoCMakeSettings,
     ) -> Result<String> {
-        if let Some(path) = worktree.which("neocmakelsp") {
-            return Ok(path);
-        }
-
-        if let Some(path) = &self.cached_binary_path {
-            if fs::metadata(path).map_or(false, |stat| stat.is_file()) {
-                return Ok(path.clone());
+        let mut outdated_path_binary = None;
+        if let Some(path) = worktree.which(SERVER_NAME) {
+            let version = release::probe_version(&path)
+                .ok()
+                .and_then(|output| output.split_whitespace().find_map(release::parse_version));
+            match version {
+                Some(version) if version < MIN_SERVER_VERSION && !settings.force_path_binary => {
+                    outdated_path_binary = Some((version, path));
+                }
+                _ => return Ok(path),
             }
         }
 
+        let managed = self.managed_binary_path(language_server_id, worktree, settings);
+        let Some((version, path)) = outdated_path_binary else {
+            return managed;
+        };
+        let (binary_path, message) = match managed {
+            Ok(binary_path) => (
+                binary_path,
+                format!(
+                    "neocmakelsp {version} on PATH is older than the supported {MIN_SERVER_VERSION}, \
+                     using a downloaded release instead"
+                ),
+            ),
+            // An old server still beats none at all.
+            Err(e) => (
+                path,
+                format!(
+                    "neocmakelsp {version} on PATH is older than the supported {MIN_SERVER_VERSION} \
+                     and no newer release could be installed ({e}), using it anyway"
+                ),
+            ),
+        };
         zed::set_language_server_installation_status(
-            &language_server_id,
-            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::Failed(message),
         );
-        let release = zed::latest_github_release(
-            "neocmakelsp/neocmakelsp",
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        )?;
+        Ok(binary_path)
+    }
 
-        let (platform, arch) = zed::current_platform();
-        let asset_name = match (platform, arch) {
-            (zed::Os::Mac, _) => "neocmakelsp-universal-apple-darwin.tar.gz",
-            (zed::Os::Windows, zed::Architecture::Aarch64) => {
-                "neocmakelsp-aarch64-pc-windows-msvc.zip"
+    fn managed_binary_path(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+        settings: &NeoCMakeSettings,
+    ) -> Result<String> {
+        let pin = match settings
+            .version
+            .as_deref()
+            .map(VersionPin::parse)
+            .transpose()
+        {
+            Ok(pin) => pin,
+            Err(e) => {
+                let e = InstallError::InvalidVersion(e);
+                install_log::record_failure(SERVER_NAME, &e);
+                let e = e.to_string();
+                zed::set_language_server_installation_status(
+                    language_server_id,
+                    &zed::LanguageServerInstallationStatus::Failed(e.clone()),
+                );
+                return Err(e);
             }
-            (zed::Os::Windows, zed::Architecture::X8664) => {
-                "neocmakelsp-x86_64-pc-windows-msvc.zip"
+        };
+
+        if let Some(pin) = &pin {
+            if let Some((_, path)) = release::installed_versions(settings.update_channel)
+                .into_iter()
+                .find(|(version, _)| pin.matches(version))
+            {
+                return Ok(self.use_installed(path));
             }
-            (zed::Os::Linux, zed::Architecture::Aarch64) => {
-                "neocmakelsp-aarch64-unknown-linux-gnu.tar.gz"
+        } else {
+            if let Some(path) = &self.cached_binary_path {
+                let same_channel = path
+                    .split('/')
+                    .next()
+                    .and_then(release::parse_install_dir)
+                    .is_some_and(|(channel, _)| channel == settings.update_channel);
+                if same_channel && fs::metadata(path).is_ok_and(|stat| stat.is_file()) {
+                    return Ok(path.clone());
+                }
             }
-            (zed::Os::Linux, zed::Architecture::X8664) => {
-                "neocmakelsp-x86_64-unknown-linux-gnu.tar.gz"
+
+            if let Some(path) = ReleaseState::load()
+                .filter(|state| state.pin.is_none() && state.channel == settings.update_channel)
+                .and_then(|state| state.fresh_binary_path(settings.update_check_interval()))
+            {
+                return Ok(self.use_installed(path));
             }
-            _ => {
-                return Err(format!(
-                    "Unsupported platform-arch combination: {:?} {:?}",
-                    platform, arch
-                ))
+        }
+
+        let binary_path =
+            match self.install_release(language_server_id, worktree, settings, pin.as_ref()) {
+                Ok(state) => {
+                    state.save().ok();
+                    release::binary_path(&state.install_dir)
+                }
+                Err(e) => {
+                    install_log::record_failure(SERVER_NAME, &e);
+                    // Keep CMake editing working offline with whatever was installed before,
+                    // preferring an install that satisfies the pin.
+                    let installed = release::installed_versions(settings.update_channel);
+                    let matching = installed.iter().position(|(version, _)| {
+                        pin.as_ref().is_none_or(|pin| pin.matches(version))
+                    });
+                    let Some((version, binary_path)) = matching
+                        .or((!installed.is_empty()).then_some(0))
+                        .map(|index| installed[index].clone())
+                    else {
+                        let e = e.to_string();
+                        zed::set_language_server_installation_status(
+                            language_server_id,
+                            &zed::LanguageServerInstallationStatus::Failed(e.clone()),
+                        );
+                        return Err(e);
+                    };
+                    let message = match (&settings.version, matching) {
+                        (Some(pin), None) => format!(
+                            "neocmakelsp {pin} could not be installed ({e}), ignoring the \
+                             `version` pin and using installed {version}"
+                        ),
+                        _ => format!("neocmakelsp update skipped ({e}), using installed {version}"),
+                    };
+                    zed::set_language_server_installation_status(
+                        language_server_id,
+                        &zed::LanguageServerInstallationStatus::Failed(message),
+                    );
+                    // Count the failed check as a check, so offline restarts start the
+                    // fallback right away instead of waiting for the network again.
+                    if matching.is_some() {
+                        fallback_state(settings, &binary_path).save().ok();
+                    }
+                    binary_path
+                }
+            };
+
+        Ok(self.use_installed(binary_path))
+    }
+
+    fn use_installed(&mut self, binary_path: String) -> String {
+        let mut manifest = store::Manifest::load();
+        manifest.touch(&binary_path);
+        manifest.save().ok();
+        self.cached_binary_path = Some(binary_path.clone());
+        binary_path
+    }
+
+    fn install_release(
+        &self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+        settings: &NeoCMakeSettings,
+        pin: Option<&VersionPin>,
+    ) -> Result<ReleaseState, InstallError> {
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+        );
+        let release = match &settings.release_source {
+            Some(source) => mirror::resolve(source, pin, settings.update_channel)
+                .map_err(InstallError::ReleaseSource)?,
+            None => {
+                release::resolve(pin, settings.update_channel).map_err(InstallError::network)?
             }
         };
-        let asset_type = match platform {
-            zed::Os::Mac | zed::Os::Linux => zed::DownloadedFileType::GzipTar,
-            zed::Os::Windows => zed::DownloadedFileType::Zip,
+
+        let host = assets::Host::detect();
+        let asset = match assets::select(&release.assets, &host, settings.asset_name.as_deref()) {
+            Ok(asset) => Some(asset),
+            // Without a prebuilt binary for this host, build it from source instead.
+            Err(_) if settings.asset_name.is_none() && worktree.which("cargo").is_some() => None,
+            Err(e) if settings.asset_name.is_some() => return Err(InstallError::MissingAsset(e)),
+            Err(e) => return Err(InstallError::UnsupportedPlatform(e)),
         };
 
-        let asset = release
-            .assets
-            .iter()
-            .find(|asset| asset.name == asset_name)
-            .ok_or_else(|| format!("no asset found matching {:?}", asset_name))?;
-
-        let version_dir = format!("neocmakelsp-{}", release.version);
-        let binary_path = format!(
-            "{version_dir}/neocmakelsp{}",
-            match platform {
-                zed::Os::Mac | zed::Os::Linux => "",
-                zed::Os::Windows => ".exe",
-            }
-        );
+        let version_dir = release::install_dir(settings.update_channel, &release.version);
+        let binary_path = release::binary_path(&version_dir);
 
-        if !fs::metadata(&binary_path).map_or(false, |stat| stat.is_file()) {
+        if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
             zed::set_language_server_installation_status(
-                &language_server_id,
+                language_server_id,
                 &zed::LanguageServerInstallationStatus::Downloading,
             );
 
-            zed::download_file(&asset.download_url, &version_dir, asset_type)
-                .map_err(|e| format!("failed to download file: {e}"))?;
+            match asset {
+                Some(asset) => {
+                    self.download_asset(settings, &release, asset, &version_dir, &binary_path)?;
+                }
+                None => {
+                    source::cargo_install(&release.version, &version_dir, &binary_path, worktree)
+                        .map_err(|e| {
+                        fs::remove_dir_all(&version_dir).ok();
+                        InstallError::Build(e)
+                    })?;
+                }
+            }
 
-            zed::make_file_executable(&binary_path)?;
+            zed::make_file_executable(&binary_path).map_err(InstallError::Permission)?;
 
-            // Remove old versions
-            let entries =
-                fs::read_dir(".").map_err(|e| format!("failed to list working directory {e}"))?;
-            for entry in entries {
-                let entry = entry.map_err(|e| format!("failed to load directory entry {e}"))?;
-                if entry.file_name().to_str() != Some(&version_dir) {
-                    fs::remove_dir_all(entry.path()).ok();
-                }
+            // Only drop the previous install once the new one is known to run.
+            if let Err(e) = release::probe_version(&binary_path) {
+                fs::remove_dir_all(&version_dir).ok();
+                return Err(InstallError::BrokenBinary(format!(
+                    "{} does not run: {e}",
+                    release.version
+                )));
             }
+
+            let source = asset.map_or_else(
+                || source::ASSET_NAME.to_string(),
+                |asset| asset.download_url.clone(),
+            );
+            install_log::record(
+                SERVER_NAME,
+                &format!("installed {} from {source}", release.version),
+            );
+
+            let now = state::now();
+            let mut manifest = store::Manifest::load();
+            manifest.record(store::Install {
+                tool: SERVER_NAME.to_string(),
+                version: release.version.clone(),
+                channel: settings.update_channel,
+                dir: version_dir.clone(),
+                source,
+                installed_at: now,
+                last_used: now,
+            });
+            manifest.collect_garbage(
+                SERVER_NAME,
+                settings.update_channel,
+                settings.keep_versions(),
+                &version_dir,
+            );
+            manifest.save().ok();
         }
 
-        self.cached_binary_path = Some(binary_path.clone());
-        Ok(binary_path)
+        Ok(ReleaseState {
+            tag: release.version,
+            asset_name: asset.map_or_else(
+                || source::ASSET_NAME.to_string(),
+                |asset| asset.name.clone(),
+            ),
+            install_dir: version_dir,
+            checked_at: state::now(),
+            pin: settings.version.clone(),
+            channel: settings.update_channel,
+        })
+    }
+
+    fn download_asset(
+        &self,
+        settings: &NeoCMakeSettings,
+        release: &zed::GithubRelease,
+        asset: &zed::GithubReleaseAsset,
+        version_dir: &str,
+        binary_path: &str,
+    ) -> Result<(), InstallError> {
+        let asset_type = assets::file_type(&asset.name);
+        // Bare binaries are written straight to the binary path.
+        let destination = match asset_type {
+            zed::DownloadedFileType::GzipTar | zed::DownloadedFileType::Zip => version_dir,
+            _ => binary_path,
+        };
+        let expected = checksum::expected(
+            release,
+            asset,
+            settings.checksum.as_deref(),
+            settings.require_checksum,
+        )?;
+
+        // Without a checksum to check first, let Zed download and unpack as usual.
+        if expected.is_none() && mirror::is_http(&asset.download_url) {
+            return zed::download_file(&asset.download_url, destination, asset_type).map_err(|e| {
+                fs::remove_dir_all(version_dir).ok();
+                InstallError::fetch(&asset.download_url, e)
+            });
+        }
+
+        let archive = mirror::fetch(&asset.download_url)
+            .map_err(|e| InstallError::fetch(&asset.download_url, e))?;
+        if let Some(expected) = expected {
+            checksum::verify(&asset.name, &archive, &expected)?;
+        }
+        archive::extract(&archive, asset_type, destination).map_err(|e| {
+            fs::remove_dir_all(version_dir).ok();
+            InstallError::unpack(e)
+        })
+    }
+}
+
+/// The state recorded when an update check failed and `binary_path` is used instead.
+fn fallback_state(settings: &NeoCMakeSettings, binary_path: &str) -> ReleaseState {
+    let install_dir = binary_path
+        .rsplit_once('/')
+        .map_or(binary_path, |(dir, _)| dir)
+        .to_string();
+    let asset_name = ReleaseState::load()
+        .filter(|state| state.install_dir == install_dir)
+        .map(|state| state.asset_name)
+        .unwrap_or_default();
+    ReleaseState {
+        tag: install_dir
+            .strip_prefix(settings.update_channel.dir_prefix())
+            .unwrap_or(&install_dir)
+            .to_string(),
+        asset_name,
+        install_dir,
+        checked_at: state::now(),
+        pin: settings.version.clone(),
+        channel: settings.update_channel,
     }
 }
 
@@ -107,6 +367,7 @@ impl zed::Extension for NeoCMakeExt {
     fn new() -> Self {
         Self {
             cached_binary_path: None,
+            cmake_language_server: CMakeLanguageServer::default(),
         }
     }
 
@@ -115,25 +376,206 @@ impl zed::Extension for NeoCMakeExt {
         language_server_id: &LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<zed::Command> {
+        if language_server_id.as_ref() == cmake_language_server::LANGUAGE_SERVER_ID {
+            return self
+                .cmake_language_server
+                .language_server_command(language_server_id, worktree);
+        }
+
+        // Never install with partially applied settings: dropping `checksum` or
+        // `release_source` would download an unverified release from GitHub.
+        let settings = NeoCMakeSettings::for_worktree(worktree).inspect_err(|e| {
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::Failed(e.clone()),
+            );
+        })?;
+        let binary_settings = LspSettings::for_worktree(SERVER_NAME, worktree)
+            .ok()
+            .and_then(|lsp_settings| lsp_settings.binary);
+        let binary_args = binary_settings
+            .as_ref()
+            .and_then(|binary_settings| binary_settings.arguments.clone());
+        let binary_env = binary_settings
+            .as_ref()
+            .and_then(|binary_settings| binary_settings.env.clone());
+
+        let command = match binary_settings.and_then(|binary_settings| binary_settings.path) {
+            Some(path) => path,
+            None => self.language_server_binary_path(language_server_id, worktree, &settings)?,
+        };
+
+        let shell_env = env::filter(
+            worktree.shell_env(),
+            settings.env_allow.as_deref(),
+            &settings.env_deny,
+        );
+        // Built from the filtered environment, so excluded variables stay excluded.
+        let cache_env = CMakeCache::for_worktree(worktree, &settings)
+            .map(|cache| cache.env(&shell_env))
+            .unwrap_or_default();
+
         Ok(zed::Command {
-            command: self.language_server_binary_path(language_server_id, worktree)?,
-            args: vec![String::from("stdio")],
-            env: Default::default(),
+            command,
+            args: binary_args.unwrap_or_else(|| vec![String::from("stdio")]),
+            env: env::server_env(
+                shell_env,
+                cache_env
+                    .into_iter()
+                    .chain(binary_env.into_iter().flatten()),
+            ),
         })
     }
 
     fn language_server_initialization_options(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<serde_json::Value>> {
+        if language_server_id.as_ref() == cmake_language_server::LANGUAGE_SERVER_ID {
+            return Ok(
+                LspSettings::for_worktree(cmake_language_server::SERVER_NAME, worktree)
+                    .ok()
+                    .and_then(|lsp_settings| lsp_settings.initialization_options),
+            );
+        }
+
+        let (options, problems) = server_options(worktree, false);
+        report_problems(language_server_id, worktree, &problems);
+        Ok(Some(options))
+    }
+
+    fn language_server_workspace_configuration(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<serde_json::Value>> {
+        if language_server_id.as_ref() == cmake_language_server::LANGUAGE_SERVER_ID {
+            return Ok(
+                LspSettings::for_worktree(cmake_language_server::SERVER_NAME, worktree)
+                    .ok()
+                    .and_then(|lsp_settings| lsp_settings.settings),
+            );
+        }
+
+        let (options, problems) = server_options(worktree, true);
+        report_problems(language_server_id, worktree, &problems);
+        Ok(Some(options))
+    }
+
+    fn language_server_additional_initialization_options(
+        &mut self,
+        _language_server_id: &LanguageServerId,
+        target_language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<serde_json::Value>> {
+        if target_language_server_id.as_ref() != clangd::LANGUAGE_SERVER_ID {
+            return Ok(None);
+        }
+        let settings = NeoCMakeSettings::for_worktree(worktree)?;
+        Ok(clangd::options(worktree, &settings))
+    }
+
+    fn language_server_additional_workspace_configuration(
         &mut self,
         _language_server_id: &LanguageServerId,
+        target_language_server_id: &LanguageServerId,
         _worktree: &zed::Worktree,
     ) -> Result<Option<serde_json::Value>> {
-        Ok(Some(serde_json::json!({
-            "format": { "enable": true },
-            "lint": { "enable": true },
-            "scan_cmake_in_package": false,
-            "semantic_token": false
-        })))
+        match target_language_server_id.as_ref() {
+            json_schema::LANGUAGE_SERVER_ID => json_schema::workspace_configuration().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn label_for_completion(
+        &self,
+        _language_server_id: &LanguageServerId,
+        completion: zed::lsp::Completion,
+    ) -> Option<zed::CodeLabel> {
+        labels::completion_label(completion)
+    }
+
+    fn label_for_symbol(
+        &self,
+        _language_server_id: &LanguageServerId,
+        symbol: zed::lsp::Symbol,
+    ) -> Option<zed::CodeLabel> {
+        labels::symbol_label(symbol)
+    }
+}
+
+/// Resolves the neocmakelsp options for a worktree: the defaults, then
+/// `initialization_options`, then project config files and, for workspace
+/// configuration, the server options under `settings`.
+fn server_options(
+    worktree: &zed::Worktree,
+    include_settings: bool,
+) -> (serde_json::Value, Vec<String>) {
+    let lsp_settings = LspSettings::for_worktree(SERVER_NAME, worktree).unwrap_or_default();
+    let (project_layers, mut problems) = project_config::load(worktree);
+
+    let mut layers = Vec::new();
+    if let Some(options) = lsp_settings.initialization_options {
+        layers.push(Layer {
+            origin: format!("lsp.{SERVER_NAME}.initialization_options"),
+            options,
+            strict: true,
+        });
+    }
+    layers.extend(project_layers);
+    if let Some(options) = lsp_settings.settings.filter(|_| include_settings) {
+        layers.push(Layer {
+            origin: format!("lsp.{SERVER_NAME}.settings"),
+            options,
+            // Most of `settings` configures the extension rather than the server.
+            strict: false,
+        });
+    }
+
+    let (options, layer_problems) = options::resolve(layers);
+    problems.extend(layer_problems);
+    (options, problems)
+}
+
+/// Shows dropped options, problems in the project's CMake presets and a build
+/// without `compile_commands.json` in the server status.
+fn report_problems(
+    language_server_id: &LanguageServerId,
+    worktree: &zed::Worktree,
+    option_problems: &[String],
+) {
+    let preset_problems: Vec<_> = presets::Presets::for_worktree(worktree)
+        .problems
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let mut sections = Vec::new();
+    if !option_problems.is_empty() {
+        sections.push(format!(
+            "ignored invalid {SERVER_NAME} options: {}",
+            option_problems.join("; ")
+        ));
+    }
+    if !preset_problems.is_empty() {
+        sections.push(format!(
+            "invalid CMake presets: {}",
+            preset_problems.join("; ")
+        ));
+    }
+    if let Some(warning) = NeoCMakeSettings::for_worktree(worktree)
+        .ok()
+        .and_then(|settings| clangd::export_warning(worktree, &settings))
+    {
+        sections.push(warning);
+    }
+    if sections.is_empty() {
+        return;
     }
+    zed::set_language_server_installation_status(
+        language_server_id,
+        &zed::LanguageServerInstallationStatus::Failed(sections.join("; ")),
+    );
 }
 
 zed::register_extension!(NeoCMakeExt);
@@ -0,0 +1,254 @@
+//! Reads the package and module search paths of a configured build from its
+//! `CMakeCache.txt`.
+
+use zed_extension_api as zed;
+
+use crate::presets::{Preset, PresetKind, Presets};
+use crate::settings::NeoCMakeSettings;
+
+/// Build directories tried after the configure presets' when `build_directory` is
+/// not set. `build` is the one used by the bundled tasks.
+const BUILD_DIRECTORIES: &[&str] = &[
+    "build",
+    "out/build",
+    "cmake-build-debug",
+    "cmake-build-release",
+];
+
+/// How `find_package` documents the `<Pkg>_DIR` entries it adds to the cache.
+const FIND_PACKAGE_HELP: &str = "The directory containing a CMake configuration file for ";
+
+pub struct CMakeCache {
+    /// Build directory the cache was read from, relative to the worktree root.
+    pub build_dir: String,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    name: String,
+    kind: String,
+    value: String,
+    /// The `//` comment CMake writes above the entry.
+    help: String,
+}
+
+impl CMakeCache {
+    /// Reads the cache of the configured build directory, or of the first preset or
+    /// default build directory that has one.
+    pub fn for_worktree(worktree: &zed::Worktree, settings: &NeoCMakeSettings) -> Option<Self> {
+        build_dir_candidates(worktree, settings)
+            .into_iter()
+            .find_map(|build_dir| {
+                let contents = worktree
+                    .read_text_file(&format!("{build_dir}/CMakeCache.txt"))
+                    .ok()?;
+                Some(Self::parse(&build_dir, &contents))
+            })
+    }
+
+    pub fn parse(build_dir: &str, contents: &str) -> Self {
+        let mut entries = Vec::new();
+        let mut help = String::new();
+        for line in contents.lines().map(str::trim) {
+            if let Some(comment) = line.strip_prefix("//") {
+                if !help.is_empty() {
+                    help.push(' ');
+                }
+                help.push_str(comment.trim());
+                continue;
+            }
+            let help = std::mem::take(&mut help);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (name, kind) = key.split_once(':').unwrap_or((key, ""));
+            entries.push(Entry {
+                name: name.trim_matches('"').to_string(),
+                kind: kind.to_string(),
+                value: value.to_string(),
+                help,
+            });
+        }
+        Self {
+            build_dir: build_dir.to_string(),
+            entries,
+        }
+    }
+
+    /// Whether a boolean cache entry is set, using CMake's notion of true.
+    pub fn is_on(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|value| {
+            matches!(
+                value.to_uppercase().as_str(),
+                "1" | "ON" | "YES" | "TRUE" | "Y"
+            ) || value.parse::<f64>().is_ok_and(|number| number != 0.0)
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.value.as_str())
+            .filter(|value| !value.is_empty() && !value.ends_with("-NOTFOUND"))
+    }
+
+    fn list(&self, name: &str) -> Vec<String> {
+        self.get(name)
+            .map(|value| {
+                value
+                    .split(';')
+                    .filter(|item| !item.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn prefix_path(&self) -> Vec<String> {
+        self.list("CMAKE_PREFIX_PATH")
+    }
+
+    pub fn module_path(&self) -> Vec<String> {
+        self.list("CMAKE_MODULE_PATH")
+    }
+
+    /// `<Pkg>_DIR` entries recorded by `find_package` in config mode.
+    pub fn package_dirs(&self) -> Vec<(&str, &str)> {
+        self.entries
+            .iter()
+            .filter(|entry| {
+                entry.kind == "PATH"
+                    && entry.name.ends_with("_DIR")
+                    && entry.help.starts_with(FIND_PACKAGE_HELP)
+            })
+            .filter_map(|entry| Some((entry.name.as_str(), self.get(&entry.name)?)))
+            .collect()
+    }
+
+    pub fn toolchain_file(&self) -> Option<&str> {
+        self.get("CMAKE_TOOLCHAIN_FILE")
+    }
+
+    /// Environment variables that make `find_package` inside neocmakelsp search the
+    /// same locations as the configured build. Prefix and module paths are prepended
+    /// to the values already in `shell_env`, which must already be filtered.
+    pub fn env(&self, shell_env: &zed::EnvVars) -> Vec<(String, String)> {
+        let (platform, _) = zed::current_platform();
+        let separator = match platform {
+            zed::Os::Windows => ";",
+            zed::Os::Mac | zed::Os::Linux => ":",
+        };
+        let with_shell = |name: &str, mut paths: Vec<String>| {
+            if let Some((_, existing)) = shell_env.iter().find(|(key, _)| key == name) {
+                paths.push(existing.clone());
+            }
+            (!paths.is_empty()).then(|| (name.to_string(), paths.join(separator)))
+        };
+
+        let mut env = Vec::new();
+        env.extend(with_shell("CMAKE_PREFIX_PATH", self.prefix_path()));
+        env.extend(with_shell("CMAKE_MODULE_PATH", self.module_path()));
+        env.extend(
+            self.package_dirs()
+                .into_iter()
+                .map(|(name, dir)| (name.to_string(), dir.to_string())),
+        );
+        if let Some(toolchain_file) = self.toolchain_file() {
+            env.push((
+                "CMAKE_TOOLCHAIN_FILE".to_string(),
+                toolchain_file.to_string(),
+            ));
+        }
+        env
+    }
+}
+
+/// The build directory of the project: the one with a `CMakeCache.txt` if it has
+/// been configured, otherwise where it would be configured first.
+pub fn active_build_dir(worktree: &zed::Worktree, settings: &NeoCMakeSettings) -> String {
+    if let Some(cache) = CMakeCache::for_worktree(worktree, settings) {
+        return cache.build_dir;
+    }
+    build_dir_candidates(worktree, settings).remove(0)
+}
+
+/// `build_directory` when set, otherwise the `binaryDir`s of the project's
+/// configure presets followed by [`BUILD_DIRECTORIES`].
+fn build_dir_candidates(worktree: &zed::Worktree, settings: &NeoCMakeSettings) -> Vec<String> {
+    if let Some(build_dir) = &settings.build_directory {
+        return vec![build_dir.trim_end_matches('/').to_string()];
+    }
+    let mut candidates = preset_build_dirs(worktree);
+    candidates.extend(BUILD_DIRECTORIES.iter().map(|dir| dir.to_string()));
+    candidates
+}
+
+/// `binaryDir`s of the visible configure presets, relative to the worktree root.
+fn preset_build_dirs(worktree: &zed::Worktree) -> Vec<String> {
+    Presets::for_worktree(worktree)
+        .visible(PresetKind::Configure)
+        .into_iter()
+        .filter_map(Preset::binary_dir)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CACHE: &str = r#"# This is the CMakeCache file.
+
+//Path to a file.
+CMAKE_TOOLCHAIN_FILE:FILEPATH=/opt/vcpkg/scripts/buildsystems/vcpkg.cmake
+
+//Semicolon separated list of prefixes
+CMAKE_PREFIX_PATH:UNINITIALIZED=/opt/qt6;/opt/local
+
+//The directory containing a CMake configuration file for Qt6.
+Qt6_DIR:PATH=/opt/qt6/lib/cmake/Qt6
+
+//The directory containing a CMake configuration file for
+// fmt.
+fmt_DIR:PATH=/usr/lib/cmake/fmt
+
+//The directory containing a CMake configuration file for ZLIB.
+ZLIB_DIR:PATH=ZLIB_DIR-NOTFOUND
+
+//Directory under which to collect all populated content
+FETCHCONTENT_BASE_DIR:PATH=/home/me/project/build/_deps
+
+//Value Computed by CMake
+project_BINARY_DIR:STATIC=/home/me/project/build
+
+CMAKE_EXPORT_COMPILE_COMMANDS:BOOL=ON
+"#;
+
+    #[test]
+    fn package_dirs_come_from_find_package() {
+        let cache = CMakeCache::parse("build", CACHE);
+        assert_eq!(
+            cache.package_dirs(),
+            [
+                ("Qt6_DIR", "/opt/qt6/lib/cmake/Qt6"),
+                ("fmt_DIR", "/usr/lib/cmake/fmt")
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_search_paths() {
+        let cache = CMakeCache::parse("build", CACHE);
+        assert_eq!(cache.prefix_path(), ["/opt/qt6", "/opt/local"]);
+        assert!(cache.module_path().is_empty());
+        assert_eq!(
+            cache.toolchain_file(),
+            Some("/opt/vcpkg/scripts/buildsystems/vcpkg.cmake")
+        );
+        assert!(cache.is_on("CMAKE_EXPORT_COMPILE_COMMANDS"));
+        assert!(!cache.is_on("CMAKE_VERBOSE_MAKEFILE"));
+    }
+}
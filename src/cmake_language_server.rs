@@ -0,0 +1,171 @@
+//! Support for regen-dev's cmake-language-server as an alternative to neocmakelsp.
+
+use std::fs;
+use zed::process::Command;
+use zed::settings::LspSettings;
+use zed::LanguageServerId;
+use zed_extension_api::{self as zed, serde_json, Result};
+
+use crate::install_error::InstallError;
+use crate::{install_log, state, store};
+
+/// Key of the server in `extension.toml`.
+pub const LANGUAGE_SERVER_ID: &str = "cmake-language-server";
+pub const SERVER_NAME: &str = "cmake-language-server";
+const PACKAGE: &str = "cmake-language-server";
+const VENV_DIR: &str = "cmake-language-server-venv";
+
+/// Extension-specific options read from `lsp.cmake-language-server.settings`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct CMakeLanguageServerSettings {
+    /// Install cmake-language-server into a virtualenv in the work dir when it is
+    /// not on PATH.
+    install: bool,
+}
+
+#[derive(Default)]
+pub struct CMakeLanguageServer {
+    cached_binary_path: Option<String>,
+}
+
+impl CMakeLanguageServer {
+    pub fn language_server_command(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<zed::Command> {
+        let lsp_settings = LspSettings::for_worktree(SERVER_NAME, worktree).unwrap_or_default();
+        let binary_settings = lsp_settings.binary;
+        let settings: CMakeLanguageServerSettings = lsp_settings
+            .settings
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| format!("invalid lsp.{SERVER_NAME}.settings: {e}"))?
+            .unwrap_or_default();
+
+        let args = binary_settings
+            .as_ref()
+            .and_then(|binary_settings| binary_settings.arguments.clone())
+            .unwrap_or_default();
+        let env = binary_settings
+            .as_ref()
+            .and_then(|binary_settings| binary_settings.env.clone())
+            .map(|env| env.into_iter().collect())
+            .unwrap_or_default();
+
+        let command = match binary_settings.and_then(|binary_settings| binary_settings.path) {
+            Some(path) => path,
+            None => self.binary_path(language_server_id, worktree, &settings)?,
+        };
+
+        Ok(zed::Command { command, args, env })
+    }
+
+    fn binary_path(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+        settings: &CMakeLanguageServerSettings,
+    ) -> Result<String> {
+        if let Some(path) = worktree.which(SERVER_NAME) {
+            return Ok(path);
+        }
+
+        if let Some(path) = &self.cached_binary_path {
+            if fs::metadata(path).is_ok_and(|stat| stat.is_file()) {
+                return Ok(path.clone());
+            }
+        }
+
+        let binary_path = venv_binary_path();
+        if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
+            if !settings.install {
+                return Err(format!(
+                    "{SERVER_NAME} is not installed. Put it on PATH or set \
+                     lsp.{SERVER_NAME}.settings.install to true to use it, or disable it with \
+                     \"language_servers\": [\"neocmakelsp\", \"!{SERVER_NAME}\"]"
+                ));
+            }
+
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::Downloading,
+            );
+            install_venv(worktree).map_err(|e| {
+                fs::remove_dir_all(VENV_DIR).ok();
+                let e = InstallError::Build(e);
+                install_log::record_failure(SERVER_NAME, &e);
+                zed::set_language_server_installation_status(
+                    language_server_id,
+                    &zed::LanguageServerInstallationStatus::Failed(e.to_string()),
+                );
+                e.to_string()
+            })?;
+            install_log::record(SERVER_NAME, &format!("installed {PACKAGE} into {VENV_DIR}"));
+
+            let now = state::now();
+            let mut manifest = store::Manifest::load();
+            manifest.record(store::Install {
+                tool: SERVER_NAME.to_string(),
+                version: "latest".to_string(),
+                channel: Default::default(),
+                dir: VENV_DIR.to_string(),
+                source: format!("pypi:{PACKAGE}"),
+                installed_at: now,
+                last_used: now,
+            });
+            manifest.save().ok();
+        }
+
+        self.cached_binary_path = Some(binary_path.clone());
+        Ok(binary_path)
+    }
+}
+
+fn venv_binary_path() -> String {
+    let (platform, _) = zed::current_platform();
+    match platform {
+        zed::Os::Mac | zed::Os::Linux => format!("{VENV_DIR}/bin/{SERVER_NAME}"),
+        zed::Os::Windows => format!("{VENV_DIR}/Scripts/{SERVER_NAME}.exe"),
+    }
+}
+
+/// Creates a virtualenv in the work dir with `uv`, falling back to `python -m venv`,
+/// and installs cmake-language-server into it.
+fn install_venv(worktree: &zed::Worktree) -> Result<()> {
+    let venv = std::env::current_dir()
+        .map_err(|e| format!("failed to resolve the extension work dir: {e}"))?
+        .join(VENV_DIR);
+    let venv = venv.to_string_lossy().into_owned();
+    let (platform, _) = zed::current_platform();
+    let python = match platform {
+        zed::Os::Mac | zed::Os::Linux => format!("{venv}/bin/python"),
+        zed::Os::Windows => format!("{venv}/Scripts/python.exe"),
+    };
+
+    if let Some(uv) = worktree.which("uv") {
+        run(Command::new(&uv).args(["venv", &venv]))?;
+        return run(Command::new(uv).args(["pip", "install", "--python", &python, PACKAGE]));
+    }
+
+    let system_python = worktree
+        .which("python3")
+        .or_else(|| worktree.which("python"))
+        .ok_or_else(|| format!("installing {SERVER_NAME} requires uv or python on PATH"))?;
+    run(Command::new(system_python).args(["-m", "venv", &venv]))?;
+    run(Command::new(python).args(["-m", "pip", "install", PACKAGE]))
+}
+
+fn run(mut command: Command) -> Result<()> {
+    let output = command.output()?;
+    if output.status == Some(0) {
+        return Ok(());
+    }
+    Err(format!(
+        "`{} {}` failed: {}",
+        command.command,
+        command.args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))
+}
@@ -0,0 +1,182 @@
+//! Selects which variables of the worktree shell environment reach neocmakelsp.
+
+use zed_extension_api as zed;
+
+/// Variables forwarded by default: search paths and package manager/toolchain settings.
+const DEFAULT_ALLOW: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "LC_*",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "XDG_*",
+    "SYSTEMROOT",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "USERPROFILE",
+    "CC",
+    "CXX",
+    "CMAKE_*",
+    "VCPKG_*",
+    "CONAN_*",
+    "PKG_CONFIG_*",
+    "LD_LIBRARY_PATH",
+    "DYLD_*",
+    "*_ROOT",
+    "*_DIR",
+];
+
+/// Variables never forwarded unless configured explicitly in `binary.env`.
+const DEFAULT_DENY: &[&str] = &[
+    "*TOKEN*",
+    "*SECRET*",
+    "*PASSWORD*",
+    "*PASSWD*",
+    "*CREDENTIAL*",
+    "*API_KEY*",
+    "*PRIVATE_KEY*",
+    "*AUTH*",
+];
+
+/// Keeps the variables of `shell_env` that pass the allow and deny lists.
+pub fn filter(shell_env: zed::EnvVars, allow: Option<&[String]>, deny: &[String]) -> zed::EnvVars {
+    let allowed = |name: &str| match allow {
+        Some(allow) => allow.iter().any(|pattern| matches(pattern, name)),
+        None => DEFAULT_ALLOW.iter().any(|pattern| matches(pattern, name)),
+    };
+    let denied = |name: &str| {
+        DEFAULT_DENY
+            .iter()
+            .copied()
+            .chain(deny.iter().map(String::as_str))
+            .any(|pattern| matches(pattern, name))
+    };
+
+    shell_env
+        .into_iter()
+        .filter(|(name, _)| allowed(name) && !denied(name))
+        .collect()
+}
+
+/// Applies `overrides` on top of the already [`filter`]ed environment.
+pub fn server_env(
+    mut env: zed::EnvVars,
+    overrides: impl IntoIterator<Item = (String, String)>,
+) -> zed::EnvVars {
+    for (name, value) in overrides {
+        env.retain(|(existing, _)| *existing != name);
+        env.push((name, value));
+    }
+    env
+}
+
+/// Case-insensitive match of a variable name against a pattern where `*` matches any run
+/// of characters.
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_ascii_uppercase();
+    let name = name.to_ascii_uppercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<_> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(names: &[&str]) -> zed::EnvVars {
+        names
+            .iter()
+            .map(|name| (name.to_string(), format!("{name}-value")))
+            .collect()
+    }
+
+    fn names(env: &zed::EnvVars) -> Vec<&str> {
+        env.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn matches_wildcards_case_insensitively() {
+        assert!(matches("CMAKE_*", "CMAKE_PREFIX_PATH"));
+        assert!(matches("*_ROOT", "Qt6_root"));
+        assert!(matches("*TOKEN*", "GITHUB_TOKEN"));
+        assert!(matches("*TOKEN*", "TOKEN"));
+        assert!(matches("PATH", "path"));
+        assert!(!matches("PATH", "PATHEXT"));
+        assert!(!matches("*_DIR", "DIRECTORY"));
+        assert!(!matches("A*B*C", "ACB"));
+    }
+
+    #[test]
+    fn default_lists_keep_toolchain_variables_and_drop_secrets() {
+        let env = filter(
+            vars(&[
+                "PATH",
+                "CMAKE_PREFIX_PATH",
+                "Qt6_DIR",
+                "EDITOR",
+                "GITHUB_TOKEN",
+                "CMAKE_AUTH_HEADER",
+                "VCPKG_API_KEY_FILE",
+                "MY_SDK_PASSWORD_DIR",
+            ]),
+            None,
+            &[],
+        );
+        assert_eq!(names(&env), ["PATH", "CMAKE_PREFIX_PATH", "Qt6_DIR"]);
+    }
+
+    #[test]
+    fn env_allow_replaces_the_defaults() {
+        let allow = vec!["PATH".to_string(), "MY_SDK_*".to_string()];
+        let deny = vec!["MY_SDK_LICENSE".to_string()];
+        let env = filter(
+            vars(&[
+                "PATH",
+                "CMAKE_PREFIX_PATH",
+                "MY_SDK_HOME",
+                "MY_SDK_LICENSE",
+                "MY_SDK_TOKEN",
+            ]),
+            Some(&allow),
+            &deny,
+        );
+        assert_eq!(names(&env), ["PATH", "MY_SDK_HOME"]);
+    }
+
+    #[test]
+    fn overrides_win() {
+        let env = server_env(
+            vars(&["PATH", "CMAKE_PREFIX_PATH"]),
+            [
+                ("CMAKE_PREFIX_PATH".to_string(), "/opt/qt6".to_string()),
+                ("GITHUB_TOKEN".to_string(), "explicit".to_string()),
+            ],
+        );
+        assert_eq!(
+            env,
+            [
+                ("PATH".to_string(), "PATH-value".to_string()),
+                ("CMAKE_PREFIX_PATH".to_string(), "/opt/qt6".to_string()),
+                ("GITHUB_TOKEN".to_string(), "explicit".to_string()),
+            ]
+        );
+    }
+}
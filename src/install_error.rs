@@ -0,0 +1,215 @@
+//! Install failures, classified so the status shown in Zed says how to fix them.
+
+use std::fmt;
+
+/// Why installing a language server failed. Displays as the underlying message
+/// followed by a hint on how to fix it.
+#[derive(Debug)]
+pub enum InstallError {
+    /// GitHub, the mirror or the download URL could not be reached.
+    Network(String),
+    /// GitHub refused the request because of API rate limiting.
+    RateLimited(String),
+    /// The configured `release_source` mirror or directory could not be read.
+    ReleaseSource(String),
+    /// The `version` setting is neither a release tag nor a semver requirement.
+    InvalidVersion(String),
+    /// The release has no asset named like the configured `asset_name`.
+    MissingAsset(String),
+    /// No release asset fits the host OS, architecture or C library.
+    UnsupportedPlatform(String),
+    /// The download does not match its SHA-256 checksum, or has none to check.
+    Checksum(String),
+    /// The downloaded archive could not be unpacked.
+    Unpack(String),
+    /// The work dir could not be written to or the binary made executable.
+    Permission(String),
+    /// Building or installing from source failed.
+    Build(String),
+    /// The installed binary does not run on this system.
+    BrokenBinary(String),
+}
+
+impl InstallError {
+    /// Classifies a failed GitHub release lookup or download.
+    pub fn network(message: String) -> Self {
+        let rate_limited = message.to_lowercase().contains("rate limit")
+            || matches!(http_status(&message), Some(403 | 429));
+        if rate_limited {
+            Self::RateLimited(message)
+        } else {
+            Self::Network(message)
+        }
+    }
+
+    /// Classifies a failure to fetch `location`, which is either a GitHub
+    /// release asset or comes from `release_source`.
+    pub fn fetch(location: &str, message: String) -> Self {
+        let from_github = ["https://github.com/", "https://api.github.com/"]
+            .iter()
+            .any(|prefix| location.starts_with(prefix))
+            || location.contains(".githubusercontent.com/");
+        if from_github {
+            Self::network(message)
+        } else {
+            Self::ReleaseSource(message)
+        }
+    }
+
+    /// Classifies a failure while writing files to the work dir.
+    pub fn unpack(message: String) -> Self {
+        if message.to_lowercase().contains("permission denied") {
+            Self::Permission(message)
+        } else {
+            Self::Unpack(message)
+        }
+    }
+
+    /// Short identifier used in the install log.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Network(_) => "network",
+            Self::RateLimited(_) => "rate-limit",
+            Self::ReleaseSource(_) => "release-source",
+            Self::InvalidVersion(_) => "invalid-version",
+            Self::MissingAsset(_) => "missing-asset",
+            Self::UnsupportedPlatform(_) => "unsupported-platform",
+            Self::Checksum(_) => "checksum",
+            Self::Unpack(_) => "unpack",
+            Self::Permission(_) => "permission",
+            Self::Build(_) => "build",
+            Self::BrokenBinary(_) => "broken-binary",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Network(message)
+            | Self::RateLimited(message)
+            | Self::ReleaseSource(message)
+            | Self::InvalidVersion(message)
+            | Self::MissingAsset(message)
+            | Self::UnsupportedPlatform(message)
+            | Self::Checksum(message)
+            | Self::Unpack(message)
+            | Self::Permission(message)
+            | Self::Build(message)
+            | Self::BrokenBinary(message) => message,
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Self::Network(_) => {
+                "Check your connection and proxy settings, or install from an internal mirror \
+                 with `release_source`."
+            }
+            Self::RateLimited(_) => {
+                "GitHub limits anonymous API requests per hour. Try again later, raise \
+                 `update_check_interval_hours`, or pin `version`."
+            }
+            Self::ReleaseSource(_) => {
+                "Check `release_source`: a mirror has to serve `index.json` and the release \
+                 assets, and a local path has to be inside the extension's work directory."
+            }
+            Self::InvalidVersion(_) => {
+                "Set `version` to a release tag such as `v0.8.22` or a semver requirement such \
+                 as `0.8.x`."
+            }
+            Self::MissingAsset(_) => "Set `asset_name` to one of the available assets listed.",
+            Self::UnsupportedPlatform(_) => {
+                "Set `asset_name` to a compatible asset, install cargo to build from source, \
+                 or put neocmakelsp on PATH."
+            }
+            Self::Checksum(_) => {
+                "The download may be corrupted or tampered with. Retry, or check the `checksum` \
+                 setting."
+            }
+            Self::Unpack(_) => "The download may be incomplete. Retry the installation.",
+            Self::Permission(_) => "Make sure the extension's work directory is writable.",
+            Self::Build(_) => {
+                "Make sure the required toolchain is installed and working, then retry."
+            }
+            Self::BrokenBinary(_) => {
+                "The release may not support this system, for example because it needs a newer \
+                 glibc. Pin an older `version` or set `asset_name` to a musl build."
+            }
+        }
+    }
+}
+
+/// Finds the HTTP status code in an error such as `status error 429` or
+/// `failed with status 403 Forbidden`.
+fn http_status(message: &str) -> Option<u16> {
+    let lowercase = message.to_lowercase();
+    let mut words = lowercase
+        .split(|char: char| !char.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty());
+    while words.by_ref().any(|word| word == "status") {
+        let status = words
+            .clone()
+            .take(2)
+            .find(|word| word.len() == 3 && word.bytes().all(|byte| byte.is_ascii_digit()));
+        if let Some(status) = status {
+            return status.parse().ok();
+        }
+    }
+    None
+}
+
+impl fmt::Display for InstallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}. {}", self.message(), self.hint())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limits_are_recognised_by_status_or_text() {
+        for message in [
+            r#"status error 403, response: {"message":"Forbidden"}"#,
+            "download failed with status 429 Too Many Requests",
+            "HTTP status: 403",
+            "API rate limit exceeded for 203.0.113.7",
+        ] {
+            assert_eq!(
+                InstallError::network(message.to_string()).kind(),
+                "rate-limit",
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn status_like_numbers_elsewhere_are_not_rate_limits() {
+        for message in [
+            "failed to download https://github.com/neocmakelsp/neocmakelsp/releases/download/v0.8.403/neocmakelsp.tar.gz",
+            "connection to 10.0.0.129:4290 timed out",
+            "status error 404",
+        ] {
+            assert_eq!(InstallError::network(message.to_string()).kind(), "network", "{message}");
+        }
+    }
+
+    #[test]
+    fn release_source_failures_have_their_own_hint() {
+        let error = InstallError::fetch(
+            "/srv/artifacts/v0.8.22/neocmakelsp.tar.gz",
+            "failed to read".to_string(),
+        );
+        assert_eq!(error.kind(), "release-source");
+        let error = InstallError::fetch(
+            "https://mirror.example.com/index.json",
+            "timed out".to_string(),
+        );
+        assert_eq!(error.kind(), "release-source");
+        let error = InstallError::fetch(
+            "https://github.com/neocmakelsp/neocmakelsp/releases/download/v0.8.22/neocmakelsp.tar.gz",
+            "timed out".to_string(),
+        );
+        assert_eq!(error.kind(), "network");
+    }
+}
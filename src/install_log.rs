@@ -0,0 +1,58 @@
+//! A small rotating log of install attempts kept in the work dir for bug reports.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use crate::install_error::InstallError;
+use crate::state;
+
+const LOG_FILE: &str = "install.log";
+const ROTATED_LOG_FILE: &str = "install.log.1";
+const MAX_LOG_SIZE: u64 = 64 * 1024;
+
+/// Appends a line for `tool`, moving the log aside once it grows too large.
+pub fn record(tool: &str, outcome: &str) {
+    if fs::metadata(LOG_FILE).is_ok_and(|stat| stat.len() > MAX_LOG_SIZE) {
+        fs::rename(LOG_FILE, ROTATED_LOG_FILE).ok();
+    }
+    let line = format!("{} {tool} {outcome}\n", timestamp(state::now()));
+    if let Ok(mut log) = OpenOptions::new().create(true).append(true).open(LOG_FILE) {
+        log.write_all(line.as_bytes()).ok();
+    }
+}
+
+pub fn record_failure(tool: &str, error: &InstallError) {
+    record(
+        tool,
+        &format!("failed [{}]: {}", error.kind(), error.message()),
+    );
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 UTC timestamp.
+fn timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // Civil-from-days, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
@@ -0,0 +1,20 @@
+//! Completion and validation of CMake presets files in Zed's JSON language server.
+
+use zed_extension_api::{serde_json, Result};
+
+/// Key of Zed's built-in JSON language server.
+pub const LANGUAGE_SERVER_ID: &str = "json-language-server";
+const PRESETS_FILES: &[&str] = &["CMakePresets.json", "CMakeUserPresets.json"];
+/// Schema of presets versions 1 through 10, bundled so it works offline.
+const PRESETS_SCHEMA: &str = include_str!("../schemas/cmake-presets.json");
+
+/// JSON language server settings associating the presets files with the schema.
+pub fn workspace_configuration() -> Result<serde_json::Value> {
+    let schema: serde_json::Value = serde_json::from_str(PRESETS_SCHEMA)
+        .map_err(|e| format!("invalid bundled CMake presets schema: {e}"))?;
+    Ok(serde_json::json!({
+        "json": {
+            "schemas": [{ "fileMatch": PRESETS_FILES, "schema": schema }]
+        }
+    }))
+}
@@ -0,0 +1,653 @@
+//! Syntax-highlighted labels for CMake completions and symbols, using the
+//! highlight names of `languages/cmake/highlights.scm`.
+
+use std::ops::Range;
+use zed::lsp::{Completion, CompletionKind, Symbol, SymbolKind};
+use zed_extension_api::{self as zed, CodeLabel, CodeLabelSpan};
+
+/// Builtin commands and a short summary of their arguments.
+const COMMAND_SIGNATURES: &[(&str, &str)] = &[
+    ("add_compile_definitions", "(definitions...)"),
+    ("add_compile_options", "(options...)"),
+    (
+        "add_custom_command",
+        "(OUTPUT outputs... COMMAND command [DEPENDS depends...])",
+    ),
+    (
+        "add_custom_target",
+        "(name [ALL] [COMMAND command] [DEPENDS depends...])",
+    ),
+    ("add_dependencies", "(target dependencies...)"),
+    (
+        "add_executable",
+        "(name [WIN32] [MACOSX_BUNDLE] [EXCLUDE_FROM_ALL] sources...)",
+    ),
+    (
+        "add_library",
+        "(name [STATIC|SHARED|MODULE|OBJECT|INTERFACE] sources...)",
+    ),
+    ("add_link_options", "(options...)"),
+    (
+        "add_subdirectory",
+        "(source_dir [binary_dir] [EXCLUDE_FROM_ALL])",
+    ),
+    ("add_test", "(NAME name COMMAND command [args...])"),
+    (
+        "block",
+        "([SCOPE_FOR POLICIES|VARIABLES] [PROPAGATE vars...])",
+    ),
+    ("break", "()"),
+    (
+        "cmake_dependent_option",
+        "(option \"help\" value depends force)",
+    ),
+    ("cmake_language", "(CALL|EVAL|DEFER args...)"),
+    (
+        "cmake_minimum_required",
+        "(VERSION min[...max] [FATAL_ERROR])",
+    ),
+    (
+        "cmake_parse_arguments",
+        "(prefix options one_value_keywords multi_value_keywords args...)",
+    ),
+    ("cmake_path", "(subcommand path_var [args...])"),
+    ("cmake_policy", "(SET|GET|PUSH|POP|VERSION args...)"),
+    ("configure_file", "(input output [COPYONLY] [@ONLY])"),
+    ("continue", "()"),
+    ("else", "([condition])"),
+    ("elseif", "(condition)"),
+    ("enable_language", "(languages... [OPTIONAL])"),
+    ("enable_testing", "()"),
+    ("endblock", "()"),
+    ("endforeach", "([var])"),
+    ("endfunction", "([name])"),
+    ("endif", "([condition])"),
+    ("endmacro", "([name])"),
+    ("endwhile", "([condition])"),
+    (
+        "execute_process",
+        "(COMMAND command [args...] [WORKING_DIRECTORY dir] [RESULT_VARIABLE var])",
+    ),
+    (
+        "export",
+        "(TARGETS targets... [NAMESPACE namespace] FILE file)",
+    ),
+    (
+        "FetchContent_Declare",
+        "(name [GIT_REPOSITORY url GIT_TAG tag] [URL url])",
+    ),
+    ("FetchContent_MakeAvailable", "(names...)"),
+    ("file", "(subcommand [args...])"),
+    ("find_file", "(var names... [PATHS paths...])"),
+    ("find_library", "(var names... [PATHS paths...])"),
+    (
+        "find_package",
+        "(package [version] [REQUIRED] [COMPONENTS components...])",
+    ),
+    ("find_path", "(var names... [PATHS paths...])"),
+    ("find_program", "(var names... [PATHS paths...])"),
+    (
+        "foreach",
+        "(var items... | var IN [LISTS lists...] [ITEMS items...])",
+    ),
+    ("function", "(name [args...])"),
+    ("get_directory_property", "(var [DIRECTORY dir] property)"),
+    (
+        "get_filename_component",
+        "(var file DIRECTORY|NAME|EXT|NAME_WE|ABSOLUTE|REALPATH)",
+    ),
+    (
+        "get_property",
+        "(var GLOBAL|DIRECTORY|TARGET|SOURCE|TEST|CACHE [name] PROPERTY property)",
+    ),
+    ("get_target_property", "(var target property)"),
+    ("if", "(condition)"),
+    ("include", "(file|module [OPTIONAL] [RESULT_VARIABLE var])"),
+    ("include_directories", "([AFTER|BEFORE] [SYSTEM] dirs...)"),
+    ("include_guard", "([DIRECTORY|GLOBAL])"),
+    (
+        "install",
+        "(TARGETS|FILES|DIRECTORY|EXPORT|SCRIPT|CODE items... [DESTINATION dir])",
+    ),
+    ("link_directories", "([AFTER|BEFORE] dirs...)"),
+    ("link_libraries", "(items...)"),
+    ("list", "(subcommand list [args...])"),
+    ("macro", "(name [args...])"),
+    ("mark_as_advanced", "([CLEAR|FORCE] vars...)"),
+    ("math", "(EXPR var expression)"),
+    (
+        "message",
+        "([STATUS|WARNING|AUTHOR_WARNING|SEND_ERROR|FATAL_ERROR] message...)",
+    ),
+    ("option", "(var \"help\" [value])"),
+    (
+        "project",
+        "(name [VERSION version] [LANGUAGES languages...])",
+    ),
+    ("return", "([PROPAGATE vars...])"),
+    (
+        "separate_arguments",
+        "(var [UNIX_COMMAND|WINDOWS_COMMAND|NATIVE_COMMAND] args)",
+    ),
+    ("set", "(var values... [PARENT_SCOPE])"),
+    ("set_directory_properties", "(PROPERTIES property value...)"),
+    (
+        "set_property",
+        "(GLOBAL|DIRECTORY|TARGET|SOURCE|TEST|CACHE [names...] PROPERTY property values...)",
+    ),
+    (
+        "set_source_files_properties",
+        "(files... PROPERTIES property value...)",
+    ),
+    (
+        "set_target_properties",
+        "(targets... PROPERTIES property value...)",
+    ),
+    (
+        "set_tests_properties",
+        "(tests... PROPERTIES property value...)",
+    ),
+    ("source_group", "(name [FILES files...] [TREE root])"),
+    ("string", "(subcommand [args...])"),
+    (
+        "target_compile_definitions",
+        "(target <PRIVATE|PUBLIC|INTERFACE> definitions...)",
+    ),
+    (
+        "target_compile_features",
+        "(target <PRIVATE|PUBLIC|INTERFACE> features...)",
+    ),
+    (
+        "target_compile_options",
+        "(target [BEFORE] <PRIVATE|PUBLIC|INTERFACE> options...)",
+    ),
+    (
+        "target_include_directories",
+        "(target [SYSTEM] [BEFORE] <PRIVATE|PUBLIC|INTERFACE> dirs...)",
+    ),
+    (
+        "target_link_directories",
+        "(target <PRIVATE|PUBLIC|INTERFACE> dirs...)",
+    ),
+    (
+        "target_link_libraries",
+        "(target <PRIVATE|PUBLIC|INTERFACE> items...)",
+    ),
+    (
+        "target_link_options",
+        "(target <PRIVATE|PUBLIC|INTERFACE> options...)",
+    ),
+    (
+        "target_precompile_headers",
+        "(target <PRIVATE|PUBLIC|INTERFACE> headers...)",
+    ),
+    (
+        "target_sources",
+        "(target <PRIVATE|PUBLIC|INTERFACE> [FILE_SET set] sources...)",
+    ),
+    ("try_compile", "(var [SOURCES sources...] [PROJECT name])"),
+    ("try_run", "(run_var compile_var [SOURCES sources...])"),
+    ("unset", "(var [CACHE|PARENT_SCOPE])"),
+    ("while", "(condition)"),
+];
+
+/// Commands that define the symbols listed in `outline.scm`, plus `set`.
+const DEFINING_COMMANDS: &[&str] = &[
+    "add_custom_target",
+    "add_executable",
+    "add_library",
+    "add_subdirectory",
+    "add_test",
+    "function",
+    "macro",
+    "option",
+    "project",
+    "set",
+];
+
+/// Arguments of target definitions worth showing next to the target name.
+const TARGET_KEYWORDS: &[&str] = &[
+    "STATIC",
+    "SHARED",
+    "MODULE",
+    "OBJECT",
+    "INTERFACE",
+    "IMPORTED",
+    "ALIAS",
+    "WIN32",
+    "MACOSX_BUNDLE",
+    "ALL",
+];
+
+/// Builtin variables that do not start with `CMAKE_` or `PROJECT_`.
+const BUILTIN_VARIABLES: &[&str] = &[
+    "ANDROID",
+    "APPLE",
+    "ARGC",
+    "ARGN",
+    "ARGV",
+    "BUILD_SHARED_LIBS",
+    "BUILD_TESTING",
+    "CYGWIN",
+    "IOS",
+    "LINUX",
+    "MINGW",
+    "MSVC",
+    "UNIX",
+    "WIN32",
+];
+
+pub fn completion_label(completion: Completion) -> Option<CodeLabel> {
+    let detail = completion.label_details.and_then(|details| details.detail);
+    label_completion(completion.label, completion.kind?, detail)
+}
+
+/// Labels a completion from its kind and the `detail` of its label details.
+fn label_completion(
+    name: String,
+    kind: CompletionKind,
+    detail: Option<String>,
+) -> Option<CodeLabel> {
+    let (highlight, signature) = match kind {
+        CompletionKind::Function | CompletionKind::Method => match keyword_highlight(&name) {
+            Some(highlight) => (highlight, signature(&name).map(String::from)),
+            None => match signature(&name) {
+                Some(signature) => ("function.builtin", Some(signature.to_string())),
+                // User functions and macros show the parameters the server provides, if any.
+                None => ("function", detail.filter(|detail| detail.starts_with('('))),
+            },
+        },
+        CompletionKind::Variable | CompletionKind::Field | CompletionKind::Property => {
+            if is_builtin_variable(&name) {
+                ("variable.builtin", None)
+            } else {
+                ("property", None)
+            }
+        }
+        CompletionKind::Keyword => (keyword_highlight(&name).unwrap_or("constant"), None),
+        CompletionKind::Constant | CompletionKind::EnumMember | CompletionKind::Value => {
+            ("constant", None)
+        }
+        _ => return None,
+    };
+
+    let mut spans = vec![CodeLabelSpan::literal(
+        name.clone(),
+        Some(highlight.to_string()),
+    )];
+    if let Some(signature) = signature {
+        spans.push(CodeLabelSpan::literal(signature, None));
+    }
+    Some(CodeLabel {
+        code: String::new(),
+        spans,
+        filter_range: (0..name.len()).into(),
+    })
+}
+
+/// Labels a symbol as its defining command, e.g. `function my_helper(ARGS...)`,
+/// `option BUILD_TESTS` or `add_library core STATIC`. Names that already include
+/// the command, such as `add_library(core STATIC src.cpp)`, are shown the same
+/// way; bare names get the command matching their kind.
+pub fn symbol_label(symbol: Symbol) -> Option<CodeLabel> {
+    let (command, args) = match split_command(&symbol.name) {
+        Some((command, args)) => (command, args),
+        None => {
+            let command = match symbol.kind {
+                SymbolKind::Function => "function",
+                SymbolKind::Method => "macro",
+                SymbolKind::Boolean => "option",
+                SymbolKind::Variable | SymbolKind::Constant => "set",
+                SymbolKind::Module | SymbolKind::Package | SymbolKind::Namespace => "project",
+                _ => return None,
+            };
+            (command.to_string(), split_args(&symbol.name))
+        }
+    };
+    let (name, rest) = args.split_first()?;
+
+    let mut label = LabelBuilder::default();
+    let command_highlight =
+        keyword_highlight(&command).unwrap_or(if signature(&command).is_some() {
+            "function.builtin"
+        } else {
+            "function"
+        });
+    label.push(&command, Some(command_highlight));
+    label.push(" ", None);
+    let name_range = match command.to_lowercase().as_str() {
+        "function" | "macro" => {
+            let name_range = label.push(name, Some("function.definition"));
+            if !rest.is_empty() {
+                label.push(&format!("({})", rest.join(" ")), None);
+            }
+            name_range
+        }
+        "add_library" | "add_executable" | "add_custom_target" => {
+            let name_range = label.push(name, Some("type"));
+            for keyword in rest
+                .iter()
+                .filter(|arg| TARGET_KEYWORDS.contains(&arg.as_str()))
+            {
+                label.push(" ", None);
+                label.push(keyword, Some("constant"));
+            }
+            name_range
+        }
+        "add_test" if name == "NAME" && !rest.is_empty() => {
+            label.push(name, Some("constant"));
+            label.push(" ", None);
+            label.push(&rest[0], None)
+        }
+        "option" | "set" if is_builtin_variable(name) => label.push(name, Some("variable.builtin")),
+        "option" | "set" => label.push(name, Some("property")),
+        _ => label.push(name, None),
+    };
+    Some(label.build(name_range))
+}
+
+/// Splits a symbol name like `add_library(core STATIC)` or `option BUILD_TESTS`
+/// into its command and arguments.
+fn split_command(name: &str) -> Option<(String, Vec<String>)> {
+    let end = name
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(name.len());
+    let (command, rest) = name.split_at(end);
+    let known = DEFINING_COMMANDS
+        .iter()
+        .any(|defining| defining.eq_ignore_ascii_case(command));
+    let rest = rest.trim_start();
+    if !known || rest.is_empty() {
+        return None;
+    }
+    Some((command.to_lowercase(), split_args(rest)))
+}
+
+/// Splits `(a b "c d")` or `a(b c)` into its whitespace-separated arguments,
+/// dropping parentheses and quotes.
+fn split_args(args: &str) -> Vec<String> {
+    args.replace(['(', ')'], " ")
+        .split_whitespace()
+        .map(|arg| arg.trim_matches('"').to_string())
+        .filter(|arg| !arg.is_empty())
+        .collect()
+}
+
+#[derive(Default)]
+struct LabelBuilder {
+    spans: Vec<CodeLabelSpan>,
+    len: usize,
+}
+
+impl LabelBuilder {
+    /// Appends a span and returns its range in the label text.
+    fn push(&mut self, text: &str, highlight: Option<&str>) -> Range<usize> {
+        let start = self.len;
+        self.len += text.len();
+        self.spans
+            .push(CodeLabelSpan::literal(text, highlight.map(String::from)));
+        start..self.len
+    }
+
+    fn build(self, filter_range: Range<usize>) -> CodeLabel {
+        CodeLabel {
+            code: String::new(),
+            spans: self.spans,
+            filter_range: filter_range.into(),
+        }
+    }
+}
+
+fn signature(command: &str) -> Option<&'static str> {
+    COMMAND_SIGNATURES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(command))
+        .map(|(_, signature)| *signature)
+}
+
+/// The highlight the grammar gives a command or argument that is a keyword.
+fn keyword_highlight(name: &str) -> Option<&'static str> {
+    let highlight = match name.to_lowercase().as_str() {
+        "if" | "elseif" | "else" | "endif" | "foreach" | "endforeach" | "while" | "endwhile"
+        | "return" | "break" | "continue" => "keyword.control",
+        "function" | "endfunction" | "macro" | "endmacro" => "keyword.function",
+        "block" | "endblock" => "keyword",
+        "on" | "off" | "true" | "false" | "yes" | "no" => "boolean",
+        _ => return None,
+    };
+    Some(highlight)
+}
+
+fn is_builtin_variable(name: &str) -> bool {
+    name.starts_with("CMAKE_")
+        || name.starts_with("PROJECT_")
+        || BUILTIN_VARIABLES.contains(&name)
+        || name
+            .strip_prefix("ARGV")
+            .is_some_and(|index| index.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The label's spans as `(text, highlight)` pairs and its filter text.
+    fn render(label: CodeLabel) -> (Vec<(String, Option<String>)>, String) {
+        let spans: Vec<_> = label
+            .spans
+            .into_iter()
+            .map(|span| match span {
+                CodeLabelSpan::Literal(literal) => (literal.text, literal.highlight_name),
+                CodeLabelSpan::CodeRange(_) => panic!("labels only use literals"),
+            })
+            .collect();
+        let text: String = spans.iter().map(|(text, _)| text.as_str()).collect();
+        let filter =
+            text[label.filter_range.start as usize..label.filter_range.end as usize].to_string();
+        (spans, filter)
+    }
+
+    fn span(text: &str, highlight: Option<&str>) -> (String, Option<String>) {
+        (text.to_string(), highlight.map(String::from))
+    }
+
+    // `CompletionLabelDetails` is not exported, so label details are passed
+    // straight to `label_completion`.
+    fn completion(label: &str, kind: CompletionKind, detail: Option<&str>) -> Option<CodeLabel> {
+        label_completion(label.to_string(), kind, detail.map(String::from))
+    }
+
+    fn symbol(name: &str, kind: SymbolKind) -> Symbol {
+        Symbol {
+            kind,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn builtin_command_shows_its_signature() {
+        let label = completion("target_link_libraries", CompletionKind::Function, None).unwrap();
+        assert_eq!(
+            render(label),
+            (
+                vec![
+                    span("target_link_libraries", Some("function.builtin")),
+                    span("(target <PRIVATE|PUBLIC|INTERFACE> items...)", None),
+                ],
+                "target_link_libraries".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn user_function_uses_label_details() {
+        let label = completion(
+            "my_helper",
+            CompletionKind::Function,
+            Some("(target ARGS...)"),
+        )
+        .unwrap();
+        assert_eq!(
+            render(label).0,
+            [
+                span("my_helper", Some("function")),
+                span("(target ARGS...)", None)
+            ]
+        );
+
+        // Details that are not a parameter list are left out.
+        let label = completion(
+            "my_helper",
+            CompletionKind::Function,
+            Some("defined in helpers.cmake"),
+        )
+        .unwrap();
+        assert_eq!(render(label).0, [span("my_helper", Some("function"))]);
+    }
+
+    #[test]
+    fn variables_are_builtin_or_user() {
+        let label = completion("CMAKE_CXX_STANDARD", CompletionKind::Variable, None).unwrap();
+        assert_eq!(
+            render(label).0,
+            [span("CMAKE_CXX_STANDARD", Some("variable.builtin"))]
+        );
+
+        let label = completion("MY_SOURCES", CompletionKind::Variable, None).unwrap();
+        assert_eq!(render(label).0, [span("MY_SOURCES", Some("property"))]);
+    }
+
+    #[test]
+    fn keywords_map_to_constants() {
+        let label = completion("PUBLIC", CompletionKind::Keyword, None).unwrap();
+        assert_eq!(render(label).0, [span("PUBLIC", Some("constant"))]);
+
+        let label = completion("ON", CompletionKind::Keyword, None).unwrap();
+        assert_eq!(render(label).0, [span("ON", Some("boolean"))]);
+
+        assert!(completion("PUBLIC", CompletionKind::Text, None).is_none());
+    }
+
+    #[test]
+    fn bare_symbol_names_get_the_command_of_their_kind() {
+        let cases = [
+            (SymbolKind::Function, "function", "function.definition"),
+            (SymbolKind::Method, "macro", "function.definition"),
+            (SymbolKind::Boolean, "option", "property"),
+            (SymbolKind::Variable, "set", "property"),
+            (SymbolKind::Constant, "set", "property"),
+            (SymbolKind::Module, "project", ""),
+            (SymbolKind::Package, "project", ""),
+            (SymbolKind::Namespace, "project", ""),
+        ];
+        for (kind, command, highlight) in cases {
+            let (spans, filter) = render(symbol_label(symbol("my_name", kind)).unwrap());
+            let command_highlight = match command {
+                "function" | "macro" => "keyword.function",
+                _ => "function.builtin",
+            };
+            assert_eq!(
+                spans,
+                [
+                    span(command, Some(command_highlight)),
+                    span(" ", None),
+                    span("my_name", (!highlight.is_empty()).then_some(highlight)),
+                ],
+                "{command}"
+            );
+            assert_eq!(filter, "my_name");
+        }
+
+        let (spans, _) =
+            render(symbol_label(symbol("BUILD_SHARED_LIBS", SymbolKind::Boolean)).unwrap());
+        assert_eq!(
+            spans[2],
+            span("BUILD_SHARED_LIBS", Some("variable.builtin"))
+        );
+        assert!(symbol_label(symbol("core", SymbolKind::Class)).is_none());
+    }
+
+    #[test]
+    fn symbol_names_with_their_command() {
+        let (spans, filter) = render(
+            symbol_label(symbol(
+                "add_library(core STATIC src.cpp)",
+                SymbolKind::Object,
+            ))
+            .unwrap(),
+        );
+        assert_eq!(
+            spans,
+            [
+                span("add_library", Some("function.builtin")),
+                span(" ", None),
+                span("core", Some("type")),
+                span(" ", None),
+                span("STATIC", Some("constant")),
+            ]
+        );
+        assert_eq!(filter, "core");
+
+        let (spans, filter) = render(
+            symbol_label(symbol(
+                "function(my_helper target ARGS)",
+                SymbolKind::Function,
+            ))
+            .unwrap(),
+        );
+        assert_eq!(
+            spans,
+            [
+                span("function", Some("keyword.function")),
+                span(" ", None),
+                span("my_helper", Some("function.definition")),
+                span("(target ARGS)", None),
+            ]
+        );
+        assert_eq!(filter, "my_helper");
+    }
+
+    #[test]
+    fn add_test_filters_on_the_test_name() {
+        let label = symbol_label(symbol(
+            "add_test(NAME unit_tests COMMAND tests)",
+            SymbolKind::Object,
+        ))
+        .unwrap();
+        assert_eq!(label.filter_range.start, "add_test NAME ".len() as u32);
+        let (spans, filter) = render(label);
+        assert_eq!(
+            spans,
+            [
+                span("add_test", Some("function.builtin")),
+                span(" ", None),
+                span("NAME", Some("constant")),
+                span(" ", None),
+                span("unit_tests", None),
+            ]
+        );
+        assert_eq!(filter, "unit_tests");
+    }
+
+    #[test]
+    fn split_command_only_knows_defining_commands() {
+        assert_eq!(
+            split_command("option BUILD_TESTS \"Build tests\" ON"),
+            Some((
+                "option".to_string(),
+                vec![
+                    "BUILD_TESTS".to_string(),
+                    "Build".to_string(),
+                    "tests".to_string(),
+                    "ON".to_string()
+                ]
+            ))
+        );
+        assert_eq!(
+            split_command("ADD_EXECUTABLE(app main.cpp)").map(|(command, _)| command),
+            Some("add_executable".to_string())
+        );
+        assert_eq!(split_command("message(STATUS hi)"), None);
+        assert_eq!(split_command("project"), None);
+    }
+}
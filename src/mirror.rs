@@ -0,0 +1,222 @@
+//! Installs neocmakelsp from an internal mirror or a local artifact directory
+//! instead of GitHub. Local directories are read directly, so they have to be
+//! inside the extension's work dir.
+//!
+//! A mirror (or directory) serves `index.json` next to one directory per tag
+//! holding the same asset names as the GitHub releases:
+//!
+//! ```text
+//! index.json    {"releases": [{"tag": "v0.8.22", "assets": ["neocmakelsp-x86_64-unknown-linux-gnu.tar.gz"]}]}
+//! v0.8.22/neocmakelsp-x86_64-unknown-linux-gnu.tar.gz
+//! ```
+
+use std::fs;
+use zed_extension_api::{self as zed, serde_json, Result};
+
+use crate::release::{self, VersionPin};
+use crate::settings::{ReleaseSource, UpdateChannel};
+
+pub const INDEX_FILE: &str = "index.json";
+
+#[derive(serde::Deserialize)]
+struct Index {
+    releases: Vec<IndexRelease>,
+}
+
+#[derive(serde::Deserialize)]
+struct IndexRelease {
+    tag: String,
+    #[serde(default)]
+    prerelease: bool,
+    assets: Vec<String>,
+}
+
+/// Returns the release from `source` matching `pin`, or its newest release on `channel`.
+pub fn resolve(
+    source: &ReleaseSource,
+    pin: Option<&VersionPin>,
+    channel: UpdateChannel,
+) -> Result<zed::GithubRelease> {
+    let base = match source {
+        ReleaseSource::Url(url) => url.trim_end_matches('/'),
+        ReleaseSource::Path(path) if is_archive_path(path) => return single_archive(path, pin),
+        ReleaseSource::Path(path) => path.trim_end_matches(['/', '\\']),
+    };
+
+    let index = fetch(&format!("{base}/{INDEX_FILE}"))?;
+    let index: Index = serde_json::from_slice(&index)
+        .map_err(|e| format!("invalid {INDEX_FILE} in {base}: {e}"))?;
+
+    let (_, release) = index
+        .releases
+        .into_iter()
+        .filter(|release| channel == UpdateChannel::Prerelease || !release.prerelease)
+        .filter_map(|release| Some((release::parse_version(&release.tag)?, release)))
+        .filter(|(version, _)| pin.is_none_or(|pin| pin.matches(version)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .ok_or_else(|| format!("no matching neocmakelsp release in {base}/{INDEX_FILE}"))?;
+
+    Ok(zed::GithubRelease {
+        assets: release
+            .assets
+            .into_iter()
+            .map(|name| zed::GithubReleaseAsset {
+                download_url: format!("{base}/{}/{name}", release.tag),
+                name,
+            })
+            .collect(),
+        version: release.tag,
+    })
+}
+
+/// A bare archive carries no version information, so it has to be pinned exactly.
+fn single_archive(path: &str, pin: Option<&VersionPin>) -> Result<zed::GithubRelease> {
+    let Some(VersionPin::Exact(version)) = pin else {
+        return Err(format!(
+            "set `version` to the exact neocmakelsp version contained in {path}"
+        ));
+    };
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    Ok(zed::GithubRelease {
+        version: format!("v{version}"),
+        assets: vec![zed::GithubReleaseAsset {
+            name: name.to_string(),
+            download_url: path.to_string(),
+        }],
+    })
+}
+
+fn is_archive_path(path: &str) -> bool {
+    [".tar.gz", ".tgz", ".zip", ".gz"]
+        .iter()
+        .any(|suffix| path.ends_with(suffix))
+}
+
+/// Whether `location` is downloaded rather than read from disk.
+pub fn is_http(location: &str) -> bool {
+    location.starts_with("https://") || location.starts_with("http://")
+}
+
+/// Reads the contents of a release asset from a URL or a local path.
+pub fn fetch(location: &str) -> Result<Vec<u8>> {
+    if is_http(location) {
+        let temp_path = format!(
+            "{}.download",
+            location.rsplit('/').next().unwrap_or("asset")
+        );
+        zed::download_file(location, &temp_path, zed::DownloadedFileType::Uncompressed)
+            .map_err(|e| format!("failed to download {location}: {e}"))?;
+        let contents = fs::read(&temp_path);
+        fs::remove_file(&temp_path).ok();
+        return contents.map_err(|e| format!("failed to read {temp_path}: {e}"));
+    }
+
+    fs::read(location).map_err(|e| {
+        format!(
+            "failed to read {location}: {e}. Local release sources have to be inside the \
+             extension's work directory, serve anything else over HTTP"
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str, index: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("neocmake-mirror-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(INDEX_FILE), index).unwrap();
+        dir.to_str().unwrap().to_string()
+    }
+
+    const INDEX: &str = r#"{ "releases": [
+        { "tag": "v0.8.20", "assets": ["neocmakelsp-x86_64-unknown-linux-gnu.tar.gz"] },
+        { "tag": "v0.8.22", "assets": ["neocmakelsp-x86_64-unknown-linux-gnu.tar.gz", "neocmakelsp-x86_64-pc-windows-msvc.zip"] },
+        { "tag": "v0.9.0-rc.1", "prerelease": true, "assets": [] },
+        { "tag": "nightly", "assets": [] }
+    ] }"#;
+
+    #[test]
+    fn resolves_newest_release_from_directory() {
+        let dir = fixture("newest", INDEX);
+        let source = ReleaseSource::Path(format!("{dir}/"));
+
+        let release = resolve(&source, None, UpdateChannel::Stable).unwrap();
+
+        assert_eq!(release.version, "v0.8.22");
+        assert_eq!(release.assets.len(), 2);
+        assert_eq!(
+            release.assets[0].name,
+            "neocmakelsp-x86_64-unknown-linux-gnu.tar.gz"
+        );
+        assert_eq!(
+            release.assets[0].download_url,
+            format!("{dir}/v0.8.22/neocmakelsp-x86_64-unknown-linux-gnu.tar.gz")
+        );
+
+        let release = resolve(&source, None, UpdateChannel::Prerelease).unwrap();
+        assert_eq!(release.version, "v0.9.0-rc.1");
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn resolves_pinned_release_from_directory() {
+        let dir = fixture("pinned", INDEX);
+        let source = ReleaseSource::Path(dir.clone());
+
+        let pin = VersionPin::parse("0.8.20").unwrap();
+        let release = resolve(&source, Some(&pin), UpdateChannel::Stable).unwrap();
+        assert_eq!(release.version, "v0.8.20");
+
+        let pin = VersionPin::parse("0.7.x").unwrap();
+        let error = resolve(&source, Some(&pin), UpdateChannel::Stable).unwrap_err();
+        assert!(error.contains("no matching neocmakelsp release"));
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn reports_invalid_index() {
+        let dir = fixture("invalid", r#"{ "releases": [{ "tag": "v0.8.22" }] }"#);
+        let error = resolve(
+            &ReleaseSource::Path(dir.clone()),
+            None,
+            UpdateChannel::Stable,
+        )
+        .unwrap_err();
+        assert!(error.starts_with(&format!("invalid {INDEX_FILE} in {dir}")));
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn reports_missing_directory() {
+        let source = ReleaseSource::Path("/nonexistent/neocmakelsp".to_string());
+        let error = resolve(&source, None, UpdateChannel::Stable).unwrap_err();
+        assert!(error.contains("inside the extension's work directory"));
+    }
+
+    #[test]
+    fn single_archive_requires_exact_pin() {
+        let source = ReleaseSource::Path(
+            "artifacts/neocmakelsp-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+        );
+
+        let pin = VersionPin::parse("0.8.22").unwrap();
+        let release = resolve(&source, Some(&pin), UpdateChannel::Stable).unwrap();
+        assert_eq!(release.version, "v0.8.22");
+        assert_eq!(
+            release.assets[0].name,
+            "neocmakelsp-x86_64-unknown-linux-gnu.tar.gz"
+        );
+        assert_eq!(
+            release.assets[0].download_url,
+            "artifacts/neocmakelsp-x86_64-unknown-linux-gnu.tar.gz"
+        );
+
+        for pin in [None, Some(VersionPin::parse("0.8.x").unwrap())] {
+            let error = resolve(&source, pin.as_ref(), UpdateChannel::Stable).unwrap_err();
+            assert!(error.contains("set `version` to the exact neocmakelsp version"));
+        }
+    }
+}
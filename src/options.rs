@@ -0,0 +1,243 @@
+//! neocmakelsp initialization options: the extension defaults, overridden by
+//! project config files and `lsp.neocmakelsp` settings.
+
+use zed_extension_api::serde_json::{self, Map, Value};
+
+enum Kind {
+    Bool,
+    Object(&'static [(&'static str, Kind)]),
+}
+
+/// The options neocmakelsp understands.
+const OPTIONS: &[(&str, Kind)] = &[
+    ("format", Kind::Object(&[("enable", Kind::Bool)])),
+    ("lint", Kind::Object(&[("enable", Kind::Bool)])),
+    ("scan_cmake_in_package", Kind::Bool),
+    ("semantic_token", Kind::Bool),
+];
+
+/// A set of option overrides.
+pub struct Layer {
+    /// Where the options come from, used when describing problems.
+    pub origin: String,
+    pub options: Value,
+    /// Whether to report keys neocmakelsp does not understand. Off for sources that
+    /// legitimately hold other keys, like the extension settings or neocmakelsp's own
+    /// config files.
+    pub strict: bool,
+}
+
+pub fn defaults() -> Value {
+    serde_json::json!({
+        "format": { "enable": true },
+        "lint": { "enable": true },
+        "scan_cmake_in_package": false,
+        "semantic_token": false
+    })
+}
+
+/// Merges the valid parts of each layer, in order, over the defaults, returning the
+/// options and a description of every value that was dropped.
+pub fn resolve(layers: impl IntoIterator<Item = Layer>) -> (Value, Vec<String>) {
+    let mut options = defaults();
+    let mut problems = Vec::new();
+    for layer in layers {
+        let valid = validate(
+            layer.options,
+            OPTIONS,
+            "",
+            &layer.origin,
+            layer.strict,
+            &mut problems,
+        );
+        merge(&mut options, valid);
+    }
+    (options, problems)
+}
+
+/// Recursively merges `overrides` into `base`, replacing everything but objects.
+pub fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+fn validate(
+    value: Value,
+    schema: &[(&str, Kind)],
+    prefix: &str,
+    origin: &str,
+    strict: bool,
+    problems: &mut Vec<String>,
+) -> Value {
+    let Value::Object(object) = value else {
+        let name = if prefix.is_empty() {
+            format!("`{origin}`")
+        } else {
+            format!("`{prefix}` in {origin}")
+        };
+        problems.push(format!(
+            "{name} should be an object, got {}",
+            type_name(&value)
+        ));
+        return Value::Object(Map::new());
+    };
+
+    let mut valid = Map::new();
+    for (key, value) in object {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match schema.iter().find(|(name, _)| *name == key) {
+            None if strict => problems.push(format!("unknown option `{path}` in {origin}")),
+            None => {}
+            Some((_, Kind::Bool)) if value.is_boolean() => {
+                valid.insert(key, value);
+            }
+            Some((_, Kind::Bool)) => problems.push(format!(
+                "`{path}` in {origin} should be a boolean, got {}",
+                type_name(&value)
+            )),
+            Some((_, Kind::Object(fields))) => {
+                valid.insert(
+                    key,
+                    validate(value, fields, &path, origin, strict, problems),
+                );
+            }
+        }
+    }
+    Value::Object(valid)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn layer(origin: &str, options: Value, strict: bool) -> Layer {
+        Layer {
+            origin: origin.to_string(),
+            options,
+            strict,
+        }
+    }
+
+    #[test]
+    fn merges_layers_in_order_over_the_defaults() {
+        let (options, problems) = resolve([
+            layer(
+                "initialization_options",
+                json!({ "format": { "enable": false }, "semantic_token": true }),
+                true,
+            ),
+            layer(
+                ".zed/neocmakelsp.json",
+                json!({ "semantic_token": false, "scan_cmake_in_package": true }),
+                true,
+            ),
+        ]);
+
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(
+            options,
+            json!({
+                "format": { "enable": false },
+                "lint": { "enable": true },
+                "scan_cmake_in_package": true,
+                "semantic_token": false
+            })
+        );
+    }
+
+    #[test]
+    fn merge_replaces_everything_but_objects() {
+        let mut base = json!({ "a": { "b": 1, "c": [1, 2] }, "d": "x" });
+        merge(
+            &mut base,
+            json!({ "a": { "c": [3], "e": true }, "d": { "f": null } }),
+        );
+        assert_eq!(
+            base,
+            json!({ "a": { "b": 1, "c": [3], "e": true }, "d": { "f": null } })
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_only_reported_for_strict_layers() {
+        let (options, problems) = resolve([
+            layer(
+                "initialization_options",
+                json!({ "formatt": { "enable": false }, "lint": { "enabled": false } }),
+                true,
+            ),
+            layer(
+                "settings",
+                json!({ "version": "0.8.x", "lint": { "enable": false } }),
+                false,
+            ),
+        ]);
+
+        assert_eq!(
+            problems,
+            [
+                "unknown option `formatt` in initialization_options",
+                "unknown option `lint.enabled` in initialization_options",
+            ]
+        );
+        assert_eq!(
+            options,
+            json!({
+                "format": { "enable": true },
+                "lint": { "enable": false },
+                "scan_cmake_in_package": false,
+                "semantic_token": false
+            })
+        );
+    }
+
+    #[test]
+    fn mistyped_values_are_reported_and_dropped() {
+        let (options, problems) = resolve([
+            layer(
+                "initialization_options",
+                json!({ "format": false, "lint": { "enable": "no" }, "semantic_token": 1 }),
+                true,
+            ),
+            layer(".neocmake.toml", json!(["not", "a", "table"]), false),
+        ]);
+
+        assert_eq!(
+            problems,
+            [
+                "`format` in initialization_options should be an object, got a boolean",
+                "`lint.enable` in initialization_options should be a boolean, got a string",
+                "`semantic_token` in initialization_options should be a boolean, got a number",
+                "`.neocmake.toml` should be an object, got an array",
+            ]
+        );
+        assert_eq!(options, defaults());
+    }
+}
@@ -0,0 +1,744 @@
+//! CMake presets read from `CMakePresets.json` and `CMakeUserPresets.json`, with
+//! `include`s followed and `inherits` resolved.
+//!
+//! Files are read through a callback, so the same code runs against a worktree
+//! and against in-memory fixtures. Problems found along the way are collected in
+//! [`Presets::problems`] for callers to report.
+
+use std::collections::HashMap;
+use std::fmt;
+use zed_extension_api as zed;
+use zed_extension_api::serde_json::{self, Map, Value};
+
+pub const PROJECT_FILE: &str = "CMakePresets.json";
+pub const USER_FILE: &str = "CMakeUserPresets.json";
+
+/// Fields describing the preset itself, which presets inheriting from it do not get.
+const NOT_INHERITED: &[&str] = &[
+    "name",
+    "hidden",
+    "inherits",
+    "displayName",
+    "description",
+    "vendor",
+    "$comment",
+];
+/// Object fields merged entry by entry instead of being inherited as a whole.
+const MERGED_FIELDS: &[&str] = &["cacheVariables", "environment"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PresetKind {
+    Configure,
+    Build,
+    Test,
+    Package,
+    Workflow,
+}
+
+impl PresetKind {
+    pub const ALL: [Self; 5] = [
+        Self::Configure,
+        Self::Build,
+        Self::Test,
+        Self::Package,
+        Self::Workflow,
+    ];
+
+    /// Key of the presets of this kind in a presets file.
+    fn key(self) -> &'static str {
+        match self {
+            Self::Configure => "configurePresets",
+            Self::Build => "buildPresets",
+            Self::Test => "testPresets",
+            Self::Package => "packagePresets",
+            Self::Workflow => "workflowPresets",
+        }
+    }
+
+    /// The kind as written in workflow steps.
+    fn step_type(self) -> &'static str {
+        match self {
+            Self::Configure => "configure",
+            Self::Build => "build",
+            Self::Test => "test",
+            Self::Package => "package",
+            Self::Workflow => "workflow",
+        }
+    }
+}
+
+impl fmt::Display for PresetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.step_type())
+    }
+}
+
+/// A 1-based line and column in a presets file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    fn at(contents: &str, offset: usize) -> Self {
+        let before = &contents[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Problem {
+    pub file: String,
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(
+                f,
+                "{}:{}:{}: {}",
+                self.file, position.line, position.column, self.message
+            ),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Preset {
+    pub kind: PresetKind,
+    pub name: String,
+    pub hidden: bool,
+    pub inherits: Vec<String>,
+    /// Fields of the preset, including the ones inherited from its parents.
+    pub fields: Map<String, Value>,
+    /// File the preset is defined in, relative to the worktree root.
+    pub file: String,
+    pub position: Option<Position>,
+    /// Whether the preset comes from `CMakeUserPresets.json` or a file it includes.
+    pub user: bool,
+}
+
+impl Preset {
+    pub fn get(&self, field: &str) -> Option<&Value> {
+        self.fields.get(field)
+    }
+
+    /// Name of the configure preset a build, test or package preset runs against.
+    pub fn configure_preset(&self) -> Option<&str> {
+        self.get("configurePreset").and_then(Value::as_str)
+    }
+
+    /// The `binaryDir` relative to the worktree root. Directories using macros
+    /// other than `${sourceDir}` and `${presetName}` are not expanded.
+    pub fn binary_dir(&self) -> Option<String> {
+        let binary_dir = self
+            .get("binaryDir")?
+            .as_str()?
+            .replace("${presetName}", &self.name)
+            .replace("${sourceDir}/", "")
+            .replace("${sourceDir}", ".");
+        let binary_dir = binary_dir.trim_end_matches('/');
+        (!binary_dir.contains('$') && !binary_dir.is_empty()).then(|| binary_dir.to_string())
+    }
+
+    fn problem(&self, message: String) -> Problem {
+        Problem {
+            file: self.file.clone(),
+            position: self.position,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Presets {
+    pub presets: Vec<Preset>,
+    pub problems: Vec<Problem>,
+}
+
+impl Presets {
+    pub fn for_worktree(worktree: &zed::Worktree) -> Self {
+        Self::load(|path| worktree.read_text_file(path).ok())
+    }
+
+    /// Reads the presets files through `read`, which returns the contents of a
+    /// path relative to the project root, or `None` when it does not exist.
+    pub fn load(read: impl Fn(&str) -> Option<String>) -> Self {
+        let mut loader = Loader {
+            read,
+            loaded: Vec::new(),
+            presets: Presets::default(),
+        };
+        loader.load_file(PROJECT_FILE, false, &mut Vec::new());
+        loader.load_file(USER_FILE, true, &mut Vec::new());
+
+        let mut presets = loader.presets;
+        presets.check_duplicates();
+        presets.resolve_inheritance();
+        presets.check_references();
+        presets
+    }
+
+    pub fn get(&self, kind: PresetKind, name: &str) -> Option<&Preset> {
+        self.presets
+            .iter()
+            .find(|preset| preset.kind == kind && preset.name == name)
+    }
+
+    /// The presets of `kind` that can be selected, user presets first.
+    pub fn visible(&self, kind: PresetKind) -> Vec<&Preset> {
+        let mut presets: Vec<_> = self
+            .presets
+            .iter()
+            .filter(|preset| preset.kind == kind && !preset.hidden)
+            .collect();
+        presets.sort_by_key(|preset| !preset.user);
+        presets
+    }
+
+    fn index_of(&self, kind: PresetKind, name: &str) -> Option<usize> {
+        self.presets
+            .iter()
+            .position(|preset| preset.kind == kind && preset.name == name)
+    }
+
+    fn check_duplicates(&mut self) {
+        let mut seen = HashMap::new();
+        for preset in &self.presets {
+            let key = (preset.kind, preset.name.as_str());
+            match seen.get(&key) {
+                Some(first) => self.problems.push(preset.problem(format!(
+                    "duplicate {} preset \"{}\", first defined in {first}",
+                    preset.kind, preset.name
+                ))),
+                None => {
+                    seen.insert(key, preset.file.as_str());
+                }
+            }
+        }
+    }
+
+    fn resolve_inheritance(&mut self) {
+        let mut resolved = HashMap::new();
+        let mut problems = Vec::new();
+        for index in 0..self.presets.len() {
+            self.resolve(index, &mut Vec::new(), &mut resolved, &mut problems);
+        }
+        for (index, fields) in resolved {
+            self.presets[index].fields = fields;
+        }
+        self.problems.extend(problems);
+    }
+
+    /// Resolves the fields of a preset and, first, of the presets it inherits from.
+    /// `stack` holds the presets being resolved, to detect cycles.
+    fn resolve(
+        &self,
+        index: usize,
+        stack: &mut Vec<usize>,
+        resolved: &mut HashMap<usize, Map<String, Value>>,
+        problems: &mut Vec<Problem>,
+    ) -> Map<String, Value> {
+        if let Some(fields) = resolved.get(&index) {
+            return fields.clone();
+        }
+        let preset = &self.presets[index];
+        if let Some(start) = stack.iter().position(|&entry| entry == index) {
+            let cycle: Vec<_> = stack[start..]
+                .iter()
+                .chain([&index])
+                .map(|&entry| self.presets[entry].name.as_str())
+                .collect();
+            problems.push(preset.problem(format!(
+                "inheritance cycle between {} presets: {}",
+                preset.kind,
+                cycle.join(" -> ")
+            )));
+            return preset.fields.clone();
+        }
+
+        stack.push(index);
+        let mut fields = preset.fields.clone();
+        for parent_name in &preset.inherits {
+            let Some(parent) = self.index_of(preset.kind, parent_name) else {
+                problems.push(preset.problem(format!(
+                    "{} preset \"{}\" inherits from unknown preset \"{parent_name}\"",
+                    preset.kind, preset.name
+                )));
+                continue;
+            };
+            if !preset.user && self.presets[parent].user {
+                problems.push(preset.problem(format!(
+                    "{} preset \"{}\" in {PROJECT_FILE} cannot inherit from user preset \"{parent_name}\"",
+                    preset.kind, preset.name
+                )));
+            }
+            let parent_fields = self.resolve(parent, stack, resolved, problems);
+            inherit(&mut fields, parent_fields);
+        }
+        stack.pop();
+
+        resolved.insert(index, fields.clone());
+        fields
+    }
+
+    /// Checks the configure presets of build, test and package presets and the
+    /// steps of workflow presets.
+    fn check_references(&mut self) {
+        let mut problems = Vec::new();
+        for preset in &self.presets {
+            match preset.kind {
+                PresetKind::Configure => {}
+                PresetKind::Build | PresetKind::Test | PresetKind::Package => {
+                    match preset.configure_preset() {
+                        Some(name) => {
+                            problems.extend(self.check_reference(
+                                preset,
+                                PresetKind::Configure,
+                                name,
+                            ));
+                        }
+                        None if !preset.hidden => problems.push(preset.problem(format!(
+                            "{} preset \"{}\" has no configurePreset",
+                            preset.kind, preset.name
+                        ))),
+                        None => {}
+                    }
+                }
+                PresetKind::Workflow => problems.extend(self.check_steps(preset)),
+            }
+        }
+        self.problems.extend(problems);
+    }
+
+    fn check_steps(&self, workflow: &Preset) -> Vec<Problem> {
+        let steps = workflow
+            .get("steps")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let mut problems = Vec::new();
+        for (index, step) in steps.iter().enumerate() {
+            let step_type = step["type"].as_str().unwrap_or_default();
+            let Some(kind) = PresetKind::ALL
+                .into_iter()
+                .filter(|kind| *kind != PresetKind::Workflow)
+                .find(|kind| kind.step_type() == step_type)
+            else {
+                problems.push(workflow.problem(format!(
+                    "workflow preset \"{}\" has a step of unknown type \"{step_type}\"",
+                    workflow.name
+                )));
+                continue;
+            };
+            if index == 0 && kind != PresetKind::Configure {
+                problems.push(workflow.problem(format!(
+                    "workflow preset \"{}\" must start with a configure step",
+                    workflow.name
+                )));
+            }
+            if let Some(name) = step["name"].as_str() {
+                problems.extend(self.check_reference(workflow, kind, name));
+            }
+        }
+        problems
+    }
+
+    /// Checks that `preset` refers to an existing, visible preset it may use.
+    fn check_reference(&self, preset: &Preset, kind: PresetKind, name: &str) -> Option<Problem> {
+        let message = match self.get(kind, name) {
+            None => format!(
+                "{} preset \"{}\" refers to unknown {kind} preset \"{name}\"",
+                preset.kind, preset.name
+            ),
+            Some(target) if target.hidden => format!(
+                "{} preset \"{}\" refers to hidden {kind} preset \"{name}\"; \
+                 hidden presets can only be inherited from",
+                preset.kind, preset.name
+            ),
+            Some(target) if target.user && !preset.user => format!(
+                "{} preset \"{}\" in {PROJECT_FILE} refers to user preset \"{name}\"",
+                preset.kind, preset.name
+            ),
+            Some(_) => return None,
+        };
+        // Hidden presets are templates, so their references are only checked
+        // in the presets that inherit them.
+        (!preset.hidden).then(|| preset.problem(message))
+    }
+}
+
+struct Loader<F> {
+    read: F,
+    loaded: Vec<String>,
+    presets: Presets,
+}
+
+impl<F: Fn(&str) -> Option<String>> Loader<F> {
+    /// Loads the presets of `path` and the files it includes. `chain` holds the
+    /// files including it. Returns whether the file exists.
+    fn load_file(&mut self, path: &str, user: bool, chain: &mut Vec<String>) -> bool {
+        if self.loaded.iter().any(|loaded| loaded == path) {
+            return true;
+        }
+        let Some(contents) = (self.read)(path) else {
+            return false;
+        };
+        self.loaded.push(path.to_string());
+
+        let root = match serde_json::from_str::<Value>(&contents) {
+            Ok(Value::Object(root)) => root,
+            Ok(_) => {
+                self.problem(
+                    path,
+                    Some(Position::at(&contents, 0)),
+                    "expected a JSON object",
+                );
+                return true;
+            }
+            Err(e) => {
+                let message = e.to_string();
+                let message = message
+                    .rsplit_once(" at line ")
+                    .map_or(message.as_str(), |(message, _)| message);
+                let position = Position {
+                    line: e.line(),
+                    column: e.column(),
+                };
+                self.problem(path, Some(position), &format!("invalid JSON: {message}"));
+                return true;
+            }
+        };
+
+        for kind in PresetKind::ALL {
+            let Some(entries) = root.get(kind.key()).and_then(Value::as_array) else {
+                continue;
+            };
+            for entry in entries {
+                let Some(fields) = entry.as_object() else {
+                    continue;
+                };
+                let Some(name) = fields.get("name").and_then(Value::as_str) else {
+                    let position = locate_key(&contents, kind.key());
+                    self.problem(path, position, &format!("a {kind} preset has no name"));
+                    continue;
+                };
+                let inherits = match fields.get("inherits") {
+                    Some(Value::String(parent)) => vec![parent.clone()],
+                    Some(Value::Array(parents)) => parents
+                        .iter()
+                        .filter_map(|parent| parent.as_str().map(String::from))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                self.presets.presets.push(Preset {
+                    kind,
+                    name: name.to_string(),
+                    hidden: fields
+                        .get("hidden")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                    inherits,
+                    fields: fields.clone(),
+                    file: path.to_string(),
+                    position: locate_field(&contents, "name", name),
+                    user,
+                });
+            }
+        }
+
+        let includes = root
+            .get("include")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        chain.push(path.to_string());
+        for include in includes.iter().filter_map(Value::as_str) {
+            let included = join(path, include);
+            let position = locate_value(&contents, "include", include);
+            if let Some(start) = chain.iter().position(|file| *file == included) {
+                let cycle = chain[start..]
+                    .iter()
+                    .map(String::as_str)
+                    .chain([included.as_str()])
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                self.problem(path, position, &format!("include cycle: {cycle}"));
+                continue;
+            }
+            if !self.load_file(&included, user, chain) {
+                self.problem(
+                    path,
+                    position,
+                    &format!("included file \"{include}\" not found"),
+                );
+            }
+        }
+        chain.pop();
+        true
+    }
+
+    fn problem(&mut self, file: &str, position: Option<Position>, message: &str) {
+        self.presets.problems.push(Problem {
+            file: file.to_string(),
+            position,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// Adds the fields of `parent` that `fields` does not set itself.
+fn inherit(fields: &mut Map<String, Value>, parent: Map<String, Value>) {
+    for (key, value) in parent {
+        if NOT_INHERITED.contains(&key.as_str()) {
+            continue;
+        }
+        match (fields.get_mut(&key), value) {
+            (Some(Value::Object(own)), Value::Object(inherited))
+                if MERGED_FIELDS.contains(&key.as_str()) =>
+            {
+                for (name, value) in inherited {
+                    own.entry(name).or_insert(value);
+                }
+            }
+            (Some(_), _) => {}
+            (None, value) => {
+                fields.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Resolves `include` relative to the directory of the file including it.
+fn join(file: &str, include: &str) -> String {
+    if include.starts_with('/') {
+        return include.to_string();
+    }
+    let mut parts: Vec<&str> = file.split('/').collect();
+    parts.pop();
+    for part in include.split('/') {
+        match part {
+            "" | "." => {}
+            ".." if parts.last().is_some_and(|last| *last != "..") => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Position of the first `"key"` in `contents`.
+fn locate_key(contents: &str, key: &str) -> Option<Position> {
+    let offset = contents.find(&format!("\"{key}\""))?;
+    Some(Position::at(contents, offset))
+}
+
+/// Position of a `"key": "value"` pair in `contents`.
+fn locate_field(contents: &str, key: &str, value: &str) -> Option<Position> {
+    let key = format!("\"{key}\"");
+    let value = serde_json::to_string(value).ok()?;
+    contents.match_indices(&key).find_map(|(offset, _)| {
+        let rest = contents[offset + key.len()..].trim_start();
+        let rest = rest.strip_prefix(':')?.trim_start();
+        rest.starts_with(&value)
+            .then(|| Position::at(contents, offset))
+    })
+}
+
+/// Position of the string `value` inside the value of the first `"key"`.
+fn locate_value(contents: &str, key: &str, value: &str) -> Option<Position> {
+    let start = contents.find(&format!("\"{key}\""))?;
+    let value = serde_json::to_string(value).ok()?;
+    let offset = start + contents[start..].find(&value)?;
+    Some(Position::at(contents, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(files: &[(&str, &str)]) -> Presets {
+        let files: HashMap<_, _> = files.iter().copied().collect();
+        Presets::load(|path| files.get(path).map(|contents| contents.to_string()))
+    }
+
+    fn problems(presets: &Presets) -> Vec<String> {
+        presets.problems.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn follows_include_chain() {
+        let presets = load(&[
+            (
+                PROJECT_FILE,
+                r#"{ "version": 6, "include": ["cmake/base.json"] }"#,
+            ),
+            (
+                "cmake/base.json",
+                r#"{ "version": 6, "include": ["ninja.json"], "configurePresets": [{ "name": "base", "hidden": true }] }"#,
+            ),
+            (
+                "cmake/ninja.json",
+                r#"{ "version": 6, "configurePresets": [{ "name": "ninja", "inherits": "base", "binaryDir": "${sourceDir}/build/${presetName}" }] }"#,
+            ),
+        ]);
+
+        assert!(presets.problems.is_empty(), "{:?}", problems(&presets));
+        let ninja = presets.get(PresetKind::Configure, "ninja").unwrap();
+        assert_eq!(ninja.file, "cmake/ninja.json");
+        assert_eq!(ninja.binary_dir().as_deref(), Some("build/ninja"));
+        let visible: Vec<_> = presets
+            .visible(PresetKind::Configure)
+            .iter()
+            .map(|preset| preset.name.as_str())
+            .collect();
+        assert_eq!(visible, ["ninja"]);
+    }
+
+    #[test]
+    fn reports_include_cycle() {
+        let presets = load(&[
+            (PROJECT_FILE, r#"{ "include": ["cmake/base.json"] }"#),
+            (
+                "cmake/base.json",
+                "{\n  \"include\": [\"../CMakePresets.json\"]\n}",
+            ),
+        ]);
+        assert_eq!(
+            problems(&presets),
+            ["cmake/base.json:2:15: include cycle: CMakePresets.json -> cmake/base.json -> CMakePresets.json"]
+        );
+    }
+
+    #[test]
+    fn first_parent_wins_across_levels() {
+        let presets = load(&[(
+            PROJECT_FILE,
+            r#"{ "configurePresets": [
+                { "name": "root", "hidden": true, "generator": "Unix Makefiles", "binaryDir": "out/root", "toolchainFile": "root.cmake" },
+                { "name": "ninja", "hidden": true, "inherits": "root", "generator": "Ninja" },
+                { "name": "clang", "hidden": true, "generator": "Xcode", "binaryDir": "out/clang" },
+                { "name": "dev", "inherits": ["ninja", "clang"], "displayName": "Dev" }
+            ] }"#,
+        )]);
+
+        assert!(presets.problems.is_empty(), "{:?}", problems(&presets));
+        let dev = presets.get(PresetKind::Configure, "dev").unwrap();
+        assert_eq!(dev.get("generator"), Some(&Value::from("Ninja")));
+        assert_eq!(dev.get("binaryDir"), Some(&Value::from("out/root")));
+        assert_eq!(dev.get("toolchainFile"), Some(&Value::from("root.cmake")));
+        assert_eq!(dev.get("displayName"), Some(&Value::from("Dev")));
+        assert!(dev.get("hidden").is_none());
+    }
+
+    #[test]
+    fn merges_cache_variables_and_environment() {
+        let presets = load(&[(
+            PROJECT_FILE,
+            r#"{ "configurePresets": [
+                { "name": "base", "hidden": true,
+                  "cacheVariables": { "CMAKE_BUILD_TYPE": "Debug", "BUILD_TESTS": "ON" },
+                  "environment": { "CC": "gcc", "CXX": "g++" } },
+                { "name": "release", "inherits": "base",
+                  "cacheVariables": { "CMAKE_BUILD_TYPE": "Release" },
+                  "environment": { "CC": "clang" } }
+            ] }"#,
+        )]);
+
+        let release = presets.get(PresetKind::Configure, "release").unwrap();
+        assert_eq!(
+            release.get("cacheVariables"),
+            Some(&serde_json::json!({ "CMAKE_BUILD_TYPE": "Release", "BUILD_TESTS": "ON" }))
+        );
+        assert_eq!(
+            release.get("environment"),
+            Some(&serde_json::json!({ "CC": "clang", "CXX": "g++" }))
+        );
+    }
+
+    #[test]
+    fn reports_missing_parent() {
+        let presets = load(&[(
+            PROJECT_FILE,
+            "{\n  \"configurePresets\": [\n    { \"name\": \"orphan\", \"inherits\": \"nope\" }\n  ]\n}",
+        )]);
+        assert_eq!(
+            problems(&presets),
+            ["CMakePresets.json:3:7: configure preset \"orphan\" inherits from unknown preset \"nope\""]
+        );
+    }
+
+    #[test]
+    fn reports_reference_to_hidden_preset() {
+        let presets = load(&[(
+            PROJECT_FILE,
+            r#"{
+                "configurePresets": [{ "name": "base", "hidden": true }],
+                "buildPresets": [
+                    { "name": "template", "hidden": true, "configurePreset": "base" },
+                    { "name": "build", "configurePreset": "base" }
+                ]
+            }"#,
+        )]);
+        assert_eq!(presets.problems.len(), 1, "{:?}", problems(&presets));
+        assert!(presets.problems[0]
+            .message
+            .starts_with("build preset \"build\" refers to hidden configure preset \"base\""));
+    }
+
+    #[test]
+    fn project_presets_cannot_inherit_from_user_presets() {
+        let presets = load(&[
+            (
+                PROJECT_FILE,
+                r#"{ "configurePresets": [{ "name": "ci", "inherits": "local" }] }"#,
+            ),
+            (
+                USER_FILE,
+                r#"{ "configurePresets": [{ "name": "local", "binaryDir": "build/local" }] }"#,
+            ),
+        ]);
+
+        assert_eq!(presets.problems.len(), 1, "{:?}", problems(&presets));
+        assert_eq!(
+            presets.problems[0].message,
+            "configure preset \"ci\" in CMakePresets.json cannot inherit from user preset \"local\""
+        );
+        let names: Vec<_> = presets
+            .visible(PresetKind::Configure)
+            .iter()
+            .map(|preset| preset.name.as_str())
+            .collect();
+        assert_eq!(names, ["local", "ci"]);
+    }
+
+    #[test]
+    fn problems_display_file_line_and_column() {
+        let presets = load(&[(PROJECT_FILE, "{\n  \"version\": 6,\n}")]);
+        assert_eq!(presets.problems.len(), 1);
+        let problem = &presets.problems[0];
+        assert_eq!(problem.position, Some(Position { line: 3, column: 1 }));
+        assert!(
+            problem
+                .to_string()
+                .starts_with("CMakePresets.json:3:1: invalid JSON: "),
+            "{problem}"
+        );
+
+        let problem = Problem {
+            file: USER_FILE.to_string(),
+            position: None,
+            message: "no position".to_string(),
+        };
+        assert_eq!(problem.to_string(), "CMakeUserPresets.json: no position");
+    }
+}
@@ -0,0 +1,74 @@
+//! Per-project neocmakelsp options read from files in the worktree.
+
+use zed_extension_api as zed;
+use zed_extension_api::serde_json::{self, Value};
+
+use crate::options::Layer;
+
+/// Config files in the order they are applied; later files win.
+const CONFIG_FILES: &[&str] = &[".neocmake.toml", ".zed/neocmakelsp.json"];
+
+/// Returns an options layer for each config file present in the worktree, and a
+/// description of each file that could not be parsed.
+pub fn load(worktree: &zed::Worktree) -> (Vec<Layer>, Vec<String>) {
+    let mut layers = Vec::new();
+    let mut problems = Vec::new();
+    for &file in CONFIG_FILES {
+        let Ok(contents) = worktree.read_text_file(file) else {
+            continue;
+        };
+        match parse(file, &contents) {
+            Ok(options) => layers.push(Layer {
+                origin: file.to_string(),
+                options,
+                // neocmakelsp reads its own TOML file too, so it holds more than our options.
+                strict: file.ends_with(".json"),
+            }),
+            Err(e) => problems.push(format!("failed to parse {file}: {e}")),
+        }
+    }
+    (layers, problems)
+}
+
+fn parse(file: &str, contents: &str) -> Result<Value, String> {
+    if file.ends_with(".json") {
+        serde_json::from_str(contents).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_neocmake_toml() {
+        let options = parse(
+            ".neocmake.toml",
+            r#"
+            scan_cmake_in_package = true # comment
+            format.enable = false
+
+            [lint]
+            enable = false
+            rules = ["line-length", 'quoted']
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            options,
+            serde_json::json!({
+                "scan_cmake_in_package": true,
+                "format": { "enable": false },
+                "lint": { "enable": false, "rules": ["line-length", "quoted"] }
+            })
+        );
+    }
+
+    #[test]
+    fn reports_toml_errors_with_line() {
+        let error = parse(".neocmake.toml", "[format]\nenable = \n").unwrap_err();
+        assert!(error.contains("line 2"), "{error}");
+    }
+}
@@ -0,0 +1,159 @@
+use std::fs;
+use zed::http_client::{HttpMethod, HttpRequest};
+use zed::process::Command;
+use zed_extension_api::{self as zed, serde_json, Result};
+
+use crate::settings::UpdateChannel;
+
+pub const REPOSITORY: &str = "neocmakelsp/neocmakelsp";
+
+/// A user-requested neocmakelsp release.
+pub enum VersionPin {
+    Exact(semver::Version),
+    Req(semver::VersionReq),
+}
+
+impl VersionPin {
+    pub fn parse(pin: &str) -> Result<Self> {
+        let pin = pin.trim();
+        if let Some(version) = parse_version(pin) {
+            return Ok(Self::Exact(version));
+        }
+        semver::VersionReq::parse(pin)
+            .map(Self::Req)
+            .map_err(|e| format!("invalid neocmakelsp version {pin:?}: {e}"))
+    }
+
+    pub fn matches(&self, version: &semver::Version) -> bool {
+        match self {
+            Self::Exact(exact) => exact == version,
+            Self::Req(req) => req.matches(version),
+        }
+    }
+}
+
+/// Parses a release tag such as `v0.8.22` into a version.
+pub fn parse_version(tag: &str) -> Option<semver::Version> {
+    semver::Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+/// Returns the release matching `pin`, or the latest one on `channel` when there is no pin.
+pub fn resolve(pin: Option<&VersionPin>, channel: UpdateChannel) -> Result<zed::GithubRelease> {
+    match pin {
+        None if channel == UpdateChannel::Stable => zed::latest_github_release(
+            REPOSITORY,
+            zed::GithubReleaseOptions {
+                require_assets: true,
+                pre_release: false,
+            },
+        ),
+        Some(VersionPin::Exact(version)) => {
+            zed::github_release_by_tag_name(REPOSITORY, &format!("v{version}"))
+                .or_else(|_| zed::github_release_by_tag_name(REPOSITORY, &version.to_string()))
+        }
+        // GitHub's newest prerelease can be an old release candidate that a
+        // stable release has since superseded, so compare the versions ourselves.
+        None | Some(VersionPin::Req(_)) => {
+            let tag = matching_tags(channel)?
+                .into_iter()
+                .filter(|(version, _)| pin.is_none_or(|pin| pin.matches(version)))
+                .max_by(|(a, _), (b, _)| a.cmp(b))
+                .map(|(_, tag)| tag)
+                .ok_or_else(|| match pin {
+                    Some(VersionPin::Req(req)) => format!("no neocmakelsp release matches {req}"),
+                    _ => "no neocmakelsp releases found".to_string(),
+                })?;
+            zed::github_release_by_tag_name(REPOSITORY, &tag)
+        }
+    }
+}
+
+/// Lists the versions and tags of all published releases on `channel`.
+fn matching_tags(channel: UpdateChannel) -> Result<Vec<(semver::Version, String)>> {
+    let response = HttpRequest::builder()
+        .method(HttpMethod::Get)
+        .url(format!(
+            "https://api.github.com/repos/{REPOSITORY}/releases?per_page=100"
+        ))
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "zed-neocmake")
+        .build()?
+        .fetch()
+        .map_err(|e| format!("failed to list neocmakelsp releases: {e}"))?;
+    let releases: Vec<serde_json::Value> = serde_json::from_slice(&response.body)
+        .map_err(|e| format!("failed to parse neocmakelsp releases: {e}"))?;
+
+    Ok(releases
+        .iter()
+        .filter(|release| release["draft"] != true)
+        .filter(|release| channel == UpdateChannel::Prerelease || release["prerelease"] != true)
+        .filter_map(|release| release["tag_name"].as_str())
+        .filter_map(|tag| Some((parse_version(tag)?, tag.to_string())))
+        .collect())
+}
+
+/// Returns the name of the work dir entry to install `tag` from `channel` into.
+pub fn install_dir(channel: UpdateChannel, tag: &str) -> String {
+    format!("{}{tag}", channel.dir_prefix())
+}
+
+/// Parses a work dir entry name created by [`install_dir`].
+pub fn parse_install_dir(name: &str) -> Option<(UpdateChannel, semver::Version)> {
+    let rest = name.strip_prefix(UpdateChannel::Stable.dir_prefix())?;
+    match rest.strip_prefix("prerelease-") {
+        Some(tag) => Some((UpdateChannel::Prerelease, parse_version(tag)?)),
+        None => Some((UpdateChannel::Stable, parse_version(rest)?)),
+    }
+}
+
+/// Returns the versions from `channel` installed in the extension work dir with
+/// their binary paths, newest first.
+pub fn installed_versions(channel: UpdateChannel) -> Vec<(semver::Version, String)> {
+    let Ok(entries) = fs::read_dir(".") else {
+        return Vec::new();
+    };
+    let mut installed: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let (installed_channel, version) = parse_install_dir(&name)?;
+            if installed_channel != channel {
+                return None;
+            }
+            let binary_path = binary_path(&name);
+            fs::metadata(&binary_path)
+                .is_ok_and(|stat| stat.is_file())
+                .then_some((version, binary_path))
+        })
+        .collect();
+    installed.sort_by(|(a, _), (b, _)| b.cmp(a));
+    installed
+}
+
+/// Runs `binary_path --version`, returning its output when the binary starts successfully.
+pub fn probe_version(binary_path: &str) -> Result<String> {
+    let command = std::env::current_dir()
+        .map(|dir| dir.join(binary_path).to_string_lossy().into_owned())
+        .unwrap_or_else(|_| binary_path.to_string());
+    let output = Command::new(command).arg("--version").output()?;
+    match output.status {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        status => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let status = status.map_or("a signal".to_string(), |code| format!("code {code}"));
+            Err(format!("exited with {status}: {}", stderr.trim()))
+        }
+    }
+}
+
+/// Returns the path of the neocmakelsp binary inside an install directory.
+pub fn binary_path(version_dir: &str) -> String {
+    let (platform, _) = zed::current_platform();
+    format!(
+        "{version_dir}/neocmakelsp{}",
+        match platform {
+            zed::Os::Mac | zed::Os::Linux => "",
+            zed::Os::Windows => ".exe",
+        }
+    )
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+use zed::settings::LspSettings;
+use zed_extension_api::{self as zed, serde_json, Result};
+
+use crate::SERVER_NAME;
+
+/// Extension-specific options read from `lsp.neocmakelsp.settings`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct NeoCMakeSettings {
+    /// Use neocmakelsp from PATH even when it is older than the supported version.
+    pub force_path_binary: bool,
+    /// Build directory whose `CMakeCache.txt` provides package and module search
+    /// paths and whose `compile_commands.json` clangd uses, relative to the
+    /// worktree root.
+    pub build_directory: Option<String>,
+    /// Shell environment variables forwarded to neocmakelsp, replacing the default
+    /// list of search path and toolchain variables. `*` matches any characters.
+    pub env_allow: Option<Vec<String>>,
+    /// Shell environment variables never forwarded, in addition to the default
+    /// list of secret-looking names.
+    pub env_deny: Vec<String>,
+    /// Release of neocmakelsp to install: an exact tag (`v0.8.22`) or a
+    /// semver requirement (`0.8.x`). Tracks the latest release when unset.
+    pub version: Option<String>,
+    /// Exact name of the release asset to download, bypassing host detection.
+    pub asset_name: Option<String>,
+    /// Expected SHA-256 of the downloaded release archive.
+    pub checksum: Option<String>,
+    /// Refuse to install releases that have no checksum to verify against.
+    pub require_checksum: bool,
+    /// Whether to track stable releases or include pre-releases.
+    pub update_channel: UpdateChannel,
+    /// Where to install neocmakelsp from instead of GitHub releases.
+    pub release_source: Option<ReleaseSource>,
+    /// How many installed versions per channel to keep for rollback. Defaults to 2.
+    pub keep_versions: Option<usize>,
+    /// How often to look for a newer release, in hours. Defaults to 24.
+    pub update_check_interval_hours: Option<u64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseSource {
+    /// Base URL of a mirror serving an `index.json` and the release assets.
+    Url(String),
+    /// Local directory laid out like a mirror, or a single release archive.
+    Path(String),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Prerelease,
+}
+
+impl UpdateChannel {
+    /// Prefix of the work dir entries holding installs from this channel.
+    pub fn dir_prefix(self) -> &'static str {
+        match self {
+            Self::Stable => "neocmakelsp-",
+            Self::Prerelease => "neocmakelsp-prerelease-",
+        }
+    }
+}
+
+impl NeoCMakeSettings {
+    pub fn for_worktree(worktree: &zed::Worktree) -> Result<Self> {
+        let settings = LspSettings::for_worktree(SERVER_NAME, worktree)
+            .ok()
+            .and_then(|lsp_settings| lsp_settings.settings);
+        match settings {
+            Some(settings) => serde_json::from_value(settings)
+                .map_err(|e| format!("invalid lsp.{SERVER_NAME}.settings: {e}")),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn keep_versions(&self) -> usize {
+        self.keep_versions.unwrap_or(2).max(1)
+    }
+
+    pub fn update_check_interval(&self) -> Duration {
+        Duration::from_secs(
+            self.update_check_interval_hours
+                .unwrap_or(24)
+                .saturating_mul(60 * 60),
+        )
+    }
+}
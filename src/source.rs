@@ -0,0 +1,47 @@
+//! Builds neocmakelsp from crates.io for hosts without a prebuilt release asset.
+
+use std::fs;
+use zed::process::Command;
+use zed_extension_api::{self as zed, Result};
+
+use crate::release;
+
+/// Recorded as the asset name of installs built by cargo.
+pub const ASSET_NAME: &str = "cargo install neocmakelsp";
+
+/// Runs `cargo install` for the release `tag` and moves the resulting binary to `binary_path`.
+pub fn cargo_install(
+    tag: &str,
+    version_dir: &str,
+    binary_path: &str,
+    worktree: &zed::Worktree,
+) -> Result<()> {
+    let cargo = worktree
+        .which("cargo")
+        .ok_or("cargo is required to build neocmakelsp from source")?;
+    let version = release::parse_version(tag).ok_or_else(|| {
+        format!("cannot build neocmakelsp {tag} from source: not a crate version")
+    })?;
+    let root = std::env::current_dir()
+        .map_err(|e| format!("failed to resolve the extension work dir: {e}"))?
+        .join(version_dir);
+
+    let output = Command::new(cargo)
+        .args(["install", "neocmakelsp", "--locked", "--version"])
+        .arg(version.to_string())
+        .arg("--root")
+        .arg(root.to_string_lossy())
+        .envs(worktree.shell_env())
+        .output()?;
+    if output.status != Some(0) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last_lines = stderr.lines().rev().take(5).collect::<Vec<_>>();
+        return Err(format!(
+            "cargo install neocmakelsp {version} failed: {}",
+            last_lines.into_iter().rev().collect::<Vec<_>>().join("\n")
+        ));
+    }
+
+    let built = binary_path.replacen(version_dir, &format!("{version_dir}/bin"), 1);
+    fs::rename(&built, binary_path).map_err(|e| format!("failed to move {built}: {e}"))
+}
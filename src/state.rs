@@ -0,0 +1,55 @@
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zed_extension_api::{serde_json, Result};
+
+use crate::release;
+use crate::settings::UpdateChannel;
+
+pub const STATE_FILE: &str = "neocmakelsp-state.json";
+
+/// The last release resolved from GitHub, persisted in the work dir so that a
+/// restart can start the installed server without querying GitHub again.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ReleaseState {
+    pub tag: String,
+    pub asset_name: String,
+    pub install_dir: String,
+    /// Seconds since the Unix epoch of the last update check, including failed
+    /// checks that fell back to an older install.
+    pub checked_at: u64,
+    /// The `version` setting the release was resolved for.
+    pub pin: Option<String>,
+    #[serde(default)]
+    pub channel: UpdateChannel,
+}
+
+impl ReleaseState {
+    pub fn load() -> Option<Self> {
+        let contents = fs::read_to_string(STATE_FILE).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(STATE_FILE, contents).map_err(|e| format!("failed to write {STATE_FILE}: {e}"))
+    }
+
+    /// Returns the installed binary if it was checked for updates less than
+    /// `interval` ago.
+    pub fn fresh_binary_path(&self, interval: Duration) -> Option<String> {
+        if now().saturating_sub(self.checked_at) >= interval.as_secs() {
+            return None;
+        }
+        let binary_path = release::binary_path(&self.install_dir);
+        fs::metadata(&binary_path)
+            .is_ok_and(|stat| stat.is_file())
+            .then_some(binary_path)
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
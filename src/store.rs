@@ -0,0 +1,221 @@
+//! Bookkeeping for the tools installed into the extension work dir.
+//!
+//! Every install is recorded in a manifest so that garbage collection only ever
+//! touches directories the extension created itself.
+
+use std::fs;
+use zed_extension_api::{serde_json, Result};
+
+use crate::release;
+use crate::settings::UpdateChannel;
+use crate::state;
+use crate::SERVER_NAME;
+
+pub const MANIFEST_FILE: &str = "installs.json";
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Manifest {
+    pub installs: Vec<Install>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Install {
+    pub tool: String,
+    pub version: String,
+    #[serde(default)]
+    pub channel: UpdateChannel,
+    /// Work dir entry holding the install.
+    pub dir: String,
+    /// Where the install came from: a download URL, a local path or a build command.
+    pub source: String,
+    pub installed_at: u64,
+    pub last_used: u64,
+}
+
+impl Manifest {
+    /// Loads the manifest, adopting neocmakelsp installs made before it existed and
+    /// dropping entries whose directory is gone.
+    pub fn load() -> Self {
+        let mut manifest: Self = fs::read_to_string(MANIFEST_FILE)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        manifest
+            .installs
+            .retain(|install| fs::metadata(&install.dir).is_ok_and(|stat| stat.is_dir()));
+
+        for entry in fs::read_dir(".").into_iter().flatten().flatten() {
+            let Ok(dir) = entry.file_name().into_string() else {
+                continue;
+            };
+            let Some((channel, _)) = release::parse_install_dir(&dir) else {
+                continue;
+            };
+            if manifest.installs.iter().any(|install| install.dir == dir) {
+                continue;
+            }
+            manifest.installs.push(Install {
+                tool: SERVER_NAME.to_string(),
+                version: dir[channel.dir_prefix().len()..].to_string(),
+                channel,
+                dir,
+                source: "unknown".to_string(),
+                installed_at: 0,
+                last_used: 0,
+            });
+        }
+        manifest
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(MANIFEST_FILE, contents)
+            .map_err(|e| format!("failed to write {MANIFEST_FILE}: {e}"))
+    }
+
+    /// Records a new install, replacing any previous entry for the same directory.
+    pub fn record(&mut self, install: Install) {
+        self.installs.retain(|existing| existing.dir != install.dir);
+        self.installs.push(install);
+    }
+
+    /// Updates the last-used time of the install containing `binary_path`.
+    pub fn touch(&mut self, binary_path: &str) {
+        let now = state::now();
+        for install in &mut self.installs {
+            if binary_path.starts_with(&format!("{}/", install.dir)) {
+                install.last_used = now;
+            }
+        }
+    }
+
+    /// Removes all but the newest `keep` installs of `tool` on `channel`, never
+    /// removing `current`.
+    pub fn collect_garbage(
+        &mut self,
+        tool: &str,
+        channel: UpdateChannel,
+        keep: usize,
+        current: &str,
+    ) {
+        let mut candidates: Vec<_> = self
+            .installs
+            .iter()
+            .filter(|install| install.tool == tool && install.channel == channel)
+            .filter(|install| install.dir != current)
+            .cloned()
+            .collect();
+        candidates.sort_by(|a, b| {
+            release::parse_version(&b.version).cmp(&release::parse_version(&a.version))
+        });
+
+        // `current` always counts towards the versions kept.
+        for install in candidates.iter().skip(keep.saturating_sub(1)) {
+            if fs::remove_dir_all(&install.dir).is_ok() {
+                self.installs.retain(|existing| existing.dir != install.dir);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("neocmake-store-{name}-{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Creates the install's directory under `root` and returns its manifest entry.
+    fn install(root: &Path, tool: &str, channel: UpdateChannel, version: &str) -> Install {
+        let dir = root.join(format!("{tool}-{version}"));
+        fs::create_dir_all(&dir).unwrap();
+        Install {
+            tool: tool.to_string(),
+            version: version.to_string(),
+            channel,
+            dir: dir.to_str().unwrap().to_string(),
+            source: "test".to_string(),
+            installed_at: 0,
+            last_used: 0,
+        }
+    }
+
+    fn versions(manifest: &Manifest) -> Vec<&str> {
+        manifest
+            .installs
+            .iter()
+            .map(|install| install.version.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn keep_counts_current() {
+        let root = scratch_dir("keep");
+        let mut manifest = Manifest::default();
+        for version in ["0.8.1", "0.8.3", "0.8.2", "0.7.0"] {
+            manifest.record(install(&root, SERVER_NAME, UpdateChannel::Stable, version));
+        }
+        // An older version being current still uses up one of the kept slots.
+        let current = manifest.installs[3].dir.clone();
+
+        manifest.collect_garbage(SERVER_NAME, UpdateChannel::Stable, 2, &current);
+
+        assert_eq!(versions(&manifest), ["0.8.3", "0.7.0"]);
+        assert!(fs::metadata(&current).is_ok());
+        assert!(fs::metadata(root.join("neocmakelsp-0.8.2")).is_err());
+        assert!(fs::metadata(root.join("neocmakelsp-0.8.1")).is_err());
+
+        // Keeping a single version leaves only `current`.
+        manifest.collect_garbage(SERVER_NAME, UpdateChannel::Stable, 1, &current);
+        assert_eq!(versions(&manifest), ["0.7.0"]);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn leaves_other_channels_and_tools_alone() {
+        let root = scratch_dir("channels");
+        let mut manifest = Manifest::default();
+        manifest.record(install(&root, SERVER_NAME, UpdateChannel::Stable, "0.8.3"));
+        manifest.record(install(&root, SERVER_NAME, UpdateChannel::Stable, "0.8.2"));
+        manifest.record(install(
+            &root,
+            SERVER_NAME,
+            UpdateChannel::Prerelease,
+            "0.9.0-beta.1",
+        ));
+        manifest.record(install(&root, "clangd", UpdateChannel::Stable, "18.1.3"));
+        let current = manifest.installs[0].dir.clone();
+
+        manifest.collect_garbage(SERVER_NAME, UpdateChannel::Stable, 1, &current);
+
+        assert_eq!(versions(&manifest), ["0.8.3", "0.9.0-beta.1", "18.1.3"]);
+        assert!(fs::metadata(root.join("neocmakelsp-0.9.0-beta.1")).is_ok());
+        assert!(fs::metadata(root.join("clangd-18.1.3")).is_ok());
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn leaves_unmanaged_entries_alone() {
+        let root = scratch_dir("unmanaged");
+        let unmanaged = root.join("neocmakelsp-0.1.0");
+        fs::create_dir_all(&unmanaged).unwrap();
+        fs::write(root.join("install.log"), "log").unwrap();
+        let mut manifest = Manifest::default();
+        manifest.record(install(&root, SERVER_NAME, UpdateChannel::Stable, "0.8.3"));
+        manifest.record(install(&root, SERVER_NAME, UpdateChannel::Stable, "0.8.2"));
+        let current = manifest.installs[0].dir.clone();
+
+        manifest.collect_garbage(SERVER_NAME, UpdateChannel::Stable, 1, &current);
+
+        assert_eq!(versions(&manifest), ["0.8.3"]);
+        assert!(fs::metadata(&unmanaged).is_ok());
+        assert!(fs::metadata(root.join("install.log")).is_ok());
+        fs::remove_dir_all(&root).ok();
+    }
+}